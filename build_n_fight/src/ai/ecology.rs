@@ -5,6 +5,7 @@
 //! Human body = MASSIVE tech spike.
 
 use super::*;
+use crate::world::NestSites;
 use bevy::prelude::*;
 
 /// Ant nest component
@@ -157,6 +158,16 @@ impl Default for AntNest {
     }
 }
 
+/// Spawn a nest at every site placed by world generation
+pub fn spawn_generated_nests(mut commands: Commands, mut nest_sites: ResMut<NestSites>) {
+    if nest_sites.sites.is_empty() {
+        return;
+    }
+    for site in nest_sites.sites.drain(..) {
+        commands.spawn((AntNest::new(), Transform::from_translation(site.as_vec3())));
+    }
+}
+
 /// System to update ecology (production, expansion, etc.)
 pub fn update_ecology(
     mut nests: Query<&mut AntNest>,
@@ -4,10 +4,20 @@
 //! 1. Follow their leader
 //! 2. Attack what's in front of them
 //! 3. Die heroically
+//!
+//! A follower whose leader has died heads for the keep on the flow field
+//! until succession finds it a new leader.
 
 use super::*;
+use crate::flow::TraversalField;
 use bevy::prelude::*;
 
+/// Tiles per second a follower closes on its formation slot (scaled by
+/// caste speed). A little quicker than leaders so stragglers catch up.
+const FOLLOW_SPEED: f32 = 3.0;
+/// Tiles per second a leaderless follower walks (scaled by caste speed)
+const STRAY_SPEED: f32 = 2.0;
+
 /// Follower component - just tracks which leader to follow
 #[derive(Component)]
 pub struct Follower {
@@ -38,10 +48,13 @@ impl Follower {
 
 /// System to update follower positions (trivial - just follow leader)
 pub fn update_followers(
-    mut followers: Query<(&Follower, &mut Transform), Without<SwarmLeader>>,
+    mut followers: Query<(&Follower, &Ant, &mut Transform), Without<SwarmLeader>>,
     leaders: Query<&Transform, With<SwarmLeader>>,
+    traversal: Res<TraversalField>,
+    footing: Footing,
+    time: Res<Time>,
 ) {
-    for (follower, mut transform) in followers.iter_mut() {
+    for (follower, ant, mut transform) in followers.iter_mut() {
         if let Ok(leader_transform) = leaders.get(follower.leader) {
             // Target position is leader position + offset
             let target = leader_transform.translation.truncate() + follower.offset;
@@ -51,10 +64,17 @@ pub fn update_followers(
             let direction = target - current;
 
             if direction.length() > 1.0 {
-                let move_speed = 50.0; // TODO: Get from ant caste
-                let movement = direction.normalize() * move_speed;
-                transform.translation.x += movement.x * 0.016; // Assuming ~60fps
-                transform.translation.y += movement.y * 0.016;
+                let pos = transform.translation.as_ivec3();
+                let max_step = FOLLOW_SPEED * footing.speed(ant.caste, pos) * time.delta_secs();
+                transform.translation += direction.clamp_length_max(max_step).extend(0.0);
+            }
+        } else {
+            // Leaderless: make for the keep until succession steps in
+            let pos = transform.translation.as_ivec3();
+            if let Some(dir) = traversal.flow_direction(pos) {
+                let max_step = STRAY_SPEED * footing.speed(ant.caste, pos) * time.delta_secs();
+                let step = (pos + dir).as_vec3() - transform.translation;
+                transform.translation += step.clamp_length_max(max_step);
             }
         }
     }
@@ -4,9 +4,18 @@
 //! - Where to attack
 //! - When to claim breach points
 //! - When to request/provide reinforcements
+//!
+//! A struggling leader posts a `ReinforcementRequest` on the breach it
+//! holds. Leaderless ants still in the tunnels are sent to it first; then
+//! the nearest idle leader brings its whole swarm, or a busy one with
+//! troops to spare hands some followers over, and either way marches to
+//! back the assault. Breaches are only ever taken through `claim_breach`.
 
 use super::*;
-use crate::flow::{BreachPoints, TargetField, TraversalField};
+use crate::combat::{DamageEvent, DamageTarget, Health};
+use crate::flow::{BreachPoints, GoalFields, GoalKey, PortalGraph, TargetField};
+use crate::world::{chunk_position, AnchorSource, GameWorld, StreamingAnchors};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
 /// Swarm leader component
@@ -14,8 +23,11 @@ use bevy::prelude::*;
 pub struct SwarmLeader {
     pub follower_count: u32,
     pub max_followers: u32,
+    /// Breach this leader holds a claim on
     pub claimed_breach: Option<Entity>,
     pub state: LeaderState,
+    /// Coarse waypoints (chunk portals) toward the current target
+    pub route: Vec<IVec3>,
 }
 
 /// Leader behavior states
@@ -36,7 +48,104 @@ impl SwarmLeader {
             max_followers,
             claimed_breach: None,
             state: LeaderState::Seeking,
+            route: Vec::new(),
+        }
+    }
+
+    /// Switch to assaulting a target, planning a coarse route to it
+    pub fn assault(&mut self, target: IVec3, from: IVec3, graph: &PortalGraph, world: &GameWorld) {
+        self.state = LeaderState::Assaulting { target };
+        self.route = graph.plan_route(world, from, target).unwrap_or_else(|| vec![target]);
+    }
+
+    /// Claim a breach and lead the assault on it. Fails if another leader
+    /// already holds it.
+    pub fn claim_breach(
+        &mut self,
+        me: Entity,
+        breach: Entity,
+        from: IVec3,
+        breach_points: &mut BreachPoints,
+        graph: &PortalGraph,
+        world: &GameWorld,
+    ) -> bool {
+        let Some(target) = breach_points.get(breach).map(|b| b.position) else {
+            return false;
+        };
+        if self.claimed_breach != Some(breach) {
+            if !breach_points.try_claim(breach, me) {
+                return false;
+            }
+            self.release_claim(me, breach_points);
+            self.claimed_breach = Some(breach);
+        }
+        self.assault(target, from, graph, world);
+        true
+    }
+
+    /// Attack a target, going through `claim_breach` if it is a breach.
+    /// Fails if another leader already holds it.
+    pub fn engage(
+        &mut self,
+        me: Entity,
+        target: IVec3,
+        from: IVec3,
+        breach_points: &mut BreachPoints,
+        graph: &PortalGraph,
+        world: &GameWorld,
+    ) -> bool {
+        if let Some(breach) = breach_points.at_position(target).map(|b| b.entity) {
+            return self.claim_breach(me, breach, from, breach_points, graph, world);
+        }
+        self.release_claim(me, breach_points);
+        self.assault(target, from, graph, world);
+        true
+    }
+
+    /// Give up any claimed breach
+    pub fn release_claim(&mut self, me: Entity, breach_points: &mut BreachPoints) {
+        if let Some(breach) = self.claimed_breach.take() {
+            if let Some(point) = breach_points.get_mut(breach) {
+                point.release(me);
+            }
+        }
+    }
+
+    /// Fall back toward the nest, releasing any claim
+    pub fn retreat(&mut self, me: Entity, breach_points: &mut BreachPoints) {
+        self.release_claim(me, breach_points);
+        self.route.clear();
+        self.state = LeaderState::Retreating;
+    }
+
+    /// Cached flow field goal for the current state (seeking leaders
+    /// follow the global `TraversalField` instead)
+    pub fn goal(&self, breach_points: &BreachPoints) -> Option<GoalKey> {
+        match self.state {
+            LeaderState::Assaulting { target } | LeaderState::Creating { target } => {
+                if breach_points.at_position(target).is_some() {
+                    Some(GoalKey::Breach(target))
+                } else {
+                    Some(GoalKey::Position(target))
+                }
+            }
+            LeaderState::Seeking | LeaderState::Reinforcing { .. } | LeaderState::Retreating => {
+                None
+            }
+        }
+    }
+
+    /// Next waypoint on the route, dropping any already reached
+    pub fn next_waypoint(&mut self, pos: IVec3) -> Option<IVec3> {
+        while let Some(next) = self.route.first() {
+            let dist = (*next - pos).abs();
+            if self.route.len() > 1 && dist.x <= 1 && dist.y <= 1 && dist.z <= 1 {
+                self.route.remove(0);
+            } else {
+                break;
+            }
         }
+        self.route.first().copied()
     }
 
     /// Check if this leader needs reinforcements
@@ -48,69 +157,771 @@ impl SwarmLeader {
     pub fn can_spare_troops(&self) -> bool {
         self.follower_count > self.max_followers * 3 / 4
     }
+
+    /// Followers needed to get back to half strength
+    pub fn shortfall(&self) -> u32 {
+        (self.max_followers / 2).saturating_sub(self.follower_count)
+    }
+
+    /// Followers that can be handed over without weakening this swarm
+    pub fn spare_troops(&self) -> u32 {
+        self.follower_count
+            .saturating_sub(self.max_followers * 3 / 4)
+    }
+
+    /// Looking for work, with a swarm fit to fight?
+    pub fn is_idle(&self) -> bool {
+        self.state == LeaderState::Seeking && !self.is_broken()
+    }
+
+    /// Is this leader free to answer a call for help?
+    pub fn can_reinforce(&self) -> bool {
+        self.is_idle()
+            || matches!(self.state, LeaderState::Assaulting { .. }) && self.can_spare_troops()
+    }
+
+    /// What the swarm is attacking, if anything
+    pub fn target(&self) -> Option<IVec3> {
+        match self.state {
+            LeaderState::Assaulting { target } | LeaderState::Creating { target } => Some(target),
+            _ => None,
+        }
+    }
+}
+
+/// Seconds between reinforcement broadcasts
+const REQUEST_INTERVAL: f32 = 2.0;
+/// How far (in tiles) a reinforcement request carries
+const REQUEST_RANGE: i32 = 40;
+
+/// A struggling leader asking for troops at the breach it holds
+#[derive(Event, Debug, Clone)]
+pub struct ReinforcementRequest {
+    pub leader: Entity,
+    pub breach: Entity,
+    pub shortfall: u32,
+}
+
+/// Tiles per second a leader moves, before caste speed
+const LEADER_SPEED: f32 = 2.0;
+/// Seconds between swarm attacks
+const ATTACK_INTERVAL: f32 = 1.0;
+/// How far (in tiles) a leader looks for its next target
+const TARGET_RANGE: i32 = 50;
+
+/// Anything a swarm can attack that isn't a tile
+type Victims<'w, 's> =
+    Query<'w, 's, (Entity, &'static Transform), (With<Health>, Without<SwarmLeader>)>;
+
+/// Where swarms can go: breaches to claim, targets worth hitting, and
+/// the routes and fields that lead there
+#[derive(SystemParam)]
+pub struct SwarmNavigation<'w> {
+    breach_points: ResMut<'w, BreachPoints>,
+    target_field: Res<'w, TargetField>,
+    portal_graph: Res<'w, PortalGraph>,
+    goal_fields: GoalFields<'w>,
+}
+
+/// What swarm attacks land on, and the pace they land at
+#[derive(SystemParam)]
+pub struct SwarmAttacks<'w, 's> {
+    victims: Victims<'w, 's>,
+    damage_events: EventWriter<'w, DamageEvent>,
+    attack_timer: Local<'s, f32>,
+}
+
+/// Within one tile of a position (in every direction)?
+fn is_near(pos: IVec3, target: IVec3) -> bool {
+    (target - pos).abs().max_element() <= 1
+}
+
+/// Outcome of a step along a flow field
+enum Step {
+    Moved,
+    /// The next tile is solid and has to be dug through
+    Blocked(IVec3),
+    /// No way on from here
+    Stuck,
+}
+
+/// Move one step in a flow direction, stopping at solid tiles
+fn step_along(
+    dir: Option<IVec3>,
+    transform: &mut Transform,
+    world: &GameWorld,
+    max_step: f32,
+) -> Step {
+    let pos = transform.translation.as_ivec3();
+    let Some(dir) = dir else {
+        return Step::Stuck;
+    };
+    let next = pos + dir;
+    if world.get_tile(next).is_some_and(|t| !t.is_passable()) {
+        return Step::Blocked(next);
+    }
+    let step = next.as_vec3() - transform.translation;
+    transform.translation += step.clamp_length_max(max_step);
+    Step::Moved
+}
+
+/// What there is to hit at a position: a destructible tile, or a unit
+fn attackable(target: IVec3, world: &GameWorld, victims: &Victims) -> Option<DamageTarget> {
+    match world.get_tile(target) {
+        Some(tile) if !tile.is_passable() => {
+            tile.is_destructible().then_some(DamageTarget::Tile(target))
+        }
+        _ => victims
+            .iter()
+            .find(|(_, transform)| transform.translation.as_ivec3() == target)
+            .map(|(victim, _)| DamageTarget::Entity(victim)),
+    }
+}
+
+/// Most valuable target in range other than `skip`
+fn next_target(target_field: &TargetField, from: IVec3, skip: IVec3) -> Option<IVec3> {
+    target_field
+        .values
+        .iter()
+        .filter(|(pos, _)| **pos != skip && (**pos - from).abs().max_element() <= TARGET_RANGE)
+        .max_by_key(|(_, value)| **value)
+        .map(|(pos, _)| *pos)
+}
+
+impl SwarmLeader {
+    /// Has the swarm lost so many followers it should fall back?
+    pub fn is_broken(&self) -> bool {
+        self.follower_count * 4 < self.max_followers
+    }
+
+    /// Damage the whole swarm deals per attack
+    pub fn swarm_damage(&self, caste: AntCaste) -> f32 {
+        caste.base_damage() as f32 * (self.follower_count + 1) as f32
+    }
+
+    /// Start digging through a tile to open a new way in
+    pub fn dig(&mut self, target: IVec3) {
+        self.route.clear();
+        self.state = LeaderState::Creating { target };
+    }
+
+    /// Go back to looking for something to attack
+    pub fn seek(&mut self) {
+        self.route.clear();
+        self.state = LeaderState::Seeking;
+    }
 }
 
 /// System to update leader behavior
+///
+/// Leaders walk the flow field toward their current goal. Anything solid
+/// in the way is dug through; a wall on the way to the player becomes a
+/// new breach. At the target the swarm attacks until nothing is left,
+/// then moves on. Swarms that lose most of their followers fall back to
+/// their nest and start over.
 pub fn update_leaders(
-    mut leaders: Query<(Entity, &mut SwarmLeader, &Transform)>,
-    breach_points: Res<BreachPoints>,
-    target_field: Res<TargetField>,
-    traversal_field: Res<TraversalField>,
+    mut leaders: Query<(Entity, &mut SwarmLeader, &mut Transform, Option<&Ant>)>,
+    nests: Query<&Transform, (With<AntNest>, Without<SwarmLeader>)>,
+    navigation: SwarmNavigation,
+    attacks: SwarmAttacks,
+    footing: Footing,
+    time: Res<Time>,
 ) {
-    for (entity, mut leader, transform) in leaders.iter_mut() {
-        let pos = transform.translation.truncate().as_ivec2().extend(0);
+    let SwarmNavigation {
+        mut breach_points,
+        target_field,
+        portal_graph,
+        mut goal_fields,
+    } = navigation;
+    let SwarmAttacks {
+        victims,
+        mut damage_events,
+        mut attack_timer,
+    } = attacks;
+    *attack_timer += time.delta_secs();
+    let attacking = *attack_timer >= ATTACK_INTERVAL;
+    if attacking {
+        *attack_timer = 0.0;
+    }
+    let world = &footing.world;
+
+    // Where each engaged leader is attacking, for reinforcements to back up
+    let allies: hashbrown::HashMap<Entity, (IVec3, GoalKey)> = leaders
+        .iter()
+        .filter_map(|(entity, leader, _, _)| {
+            Some((entity, (leader.target()?, leader.goal(&breach_points)?)))
+        })
+        .collect();
+
+    for (entity, mut leader, mut transform, ant) in leaders.iter_mut() {
+        let pos = transform.translation.as_ivec3();
+        let caste = ant.map_or(AntCaste::Median, |ant| ant.caste);
+        let home = ant
+            .and_then(|ant| nests.get(ant.home_nest).ok())
+            .or_else(|| {
+                nests
+                    .iter()
+                    .min_by_key(|t| (t.translation.as_ivec3() - pos).abs().element_sum())
+            })
+            .map(|t| t.translation.as_ivec3());
+
+        let max_step = LEADER_SPEED * footing.speed(caste, pos) * time.delta_secs();
+        let damage = leader.swarm_damage(caste);
+        let mut hit = |target: DamageTarget, position: IVec3| {
+            if attacking {
+                damage_events.send(DamageEvent {
+                    target,
+                    amount: damage,
+                    source: Some(entity),
+                    position,
+                });
+            }
+        };
 
-        match &leader.state {
+        match leader.state.clone() {
             LeaderState::Seeking => {
+                // Too few to attack - regroup at the nest
+                if leader.is_broken() {
+                    if home.is_some_and(|home| !is_near(pos, home)) {
+                        leader.retreat(entity, &mut breach_points);
+                    }
+                    continue;
+                }
+
                 // Priority 1: Claim unclaimed breach if nearby
-                if let Some(breach) = breach_points.nearest_unclaimed(pos) {
-                    let dist = (breach.position - pos).abs();
-                    if dist.x <= 10 && dist.y <= 10 {
-                        leader.state = LeaderState::Assaulting {
-                            target: breach.position,
-                        };
+                if let Some((breach, at)) =
+                    breach_points.nearest_unclaimed(pos).map(|b| (b.entity, b.position))
+                {
+                    let dist = (at - pos).abs();
+                    if dist.x <= 10
+                        && dist.y <= 10
+                        && leader.claim_breach(
+                            entity,
+                            breach,
+                            pos,
+                            &mut breach_points,
+                            &portal_graph,
+                            world,
+                        )
+                    {
                         continue;
                     }
                 }
 
                 // Priority 2: Find high-value target
-                if let Some((target_pos, _)) = target_field.highest_value_target(pos, 50) {
-                    leader.state = LeaderState::Assaulting { target: target_pos };
-                    continue;
+                if let Some((target_pos, _)) =
+                    target_field.highest_value_target(pos, TARGET_RANGE)
+                {
+                    if leader.engage(
+                        entity,
+                        target_pos,
+                        pos,
+                        &mut breach_points,
+                        &portal_graph,
+                        world,
+                    ) {
+                        continue;
+                    }
                 }
 
-                // Priority 3: Wander toward player base (TODO: implement)
+                // Priority 3: Head for the player base, breaking in where blocked
+                let dir = goal_fields.traversal().flow_direction(pos);
+                if let Step::Blocked(wall) = step_along(dir, &mut transform, world, max_step) {
+                    leader.dig(wall);
+                }
             }
 
             LeaderState::Assaulting { target } => {
-                // Check if we've reached the target
-                let dist = (*target - pos).abs();
-                if dist.x <= 1 && dist.y <= 1 && dist.z <= 1 {
-                    // At target - attack or transition
-                    // TODO: Implement attack behavior
+                // Our breach was sealed - look for something else
+                if let Some(breach) = leader.claimed_breach {
+                    if breach_points.get(breach).is_none() {
+                        leader.claimed_breach = None;
+                        leader.seek();
+                        continue;
+                    }
+                }
+
+                // Lost too many - fall back
+                if leader.is_broken() {
+                    info!(
+                        "Leader {:?} retreating with {} followers",
+                        entity, leader.follower_count
+                    );
+                    leader.retreat(entity, &mut breach_points);
+                    continue;
+                }
+
+                // At the target - attack until nothing is left, then push on
+                if is_near(pos, target) {
+                    match attackable(target, world, &victims) {
+                        Some(victim) => hit(victim, target),
+                        None => {
+                            let engaged = next_target(&target_field, pos, target).is_some_and(
+                                |next| {
+                                    leader.engage(
+                                        entity,
+                                        next,
+                                        pos,
+                                        &mut breach_points,
+                                        &portal_graph,
+                                        world,
+                                    )
+                                },
+                            );
+                            if !engaged {
+                                leader.release_claim(entity, &mut breach_points);
+                                leader.seek();
+                            }
+                        }
+                    }
+                    continue;
                 }
 
-                // Check if we've lost too many troops
-                if leader.needs_reinforcements() {
-                    // Request reinforcements but keep attacking
-                    // TODO: Broadcast reinforcement request
+                // On a long route, steer for the next portal and only path
+                // finely through this chunk and the waypoint's
+                let waypoint = leader.next_waypoint(pos).filter(|_| leader.route.len() > 1);
+                let goal = match waypoint {
+                    Some(tile) => GoalKey::Waypoint {
+                        tile,
+                        from: chunk_position(pos),
+                    },
+                    None => match leader.goal(&breach_points) {
+                        Some(goal) => goal,
+                        None => continue,
+                    },
+                };
+                let field = goal_fields.get(goal);
+                let dir = field.flow_direction(pos);
+                match step_along(dir, &mut transform, world, max_step) {
+                    Step::Blocked(wall) => leader.dig(wall),
+                    // Strayed off the route - plan a new one from here
+                    Step::Stuck if waypoint.is_some() => {
+                        leader.assault(target, pos, &portal_graph, world);
+                    }
+                    _ => {}
                 }
             }
 
             LeaderState::Reinforcing { ally } => {
-                // Move toward ally's position
-                // TODO: Implement reinforcement movement
+                if leader.is_broken() {
+                    leader.retreat(entity, &mut breach_points);
+                    continue;
+                }
+
+                // Back the ally's assault; once it is over, look for work
+                let Some((target, goal)) = allies.get(&ally).copied() else {
+                    leader.seek();
+                    continue;
+                };
+                if is_near(pos, target) {
+                    if let Some(victim) = attackable(target, world, &victims) {
+                        hit(victim, target);
+                    }
+                    continue;
+                }
+
+                let field = goal_fields.get(goal);
+                let dir = field.flow_direction(pos);
+                if let Step::Blocked(wall) = step_along(dir, &mut transform, world, max_step) {
+                    if let Some(victim) = attackable(wall, world, &victims) {
+                        hit(victim, wall);
+                    }
+                }
             }
 
             LeaderState::Creating { target } => {
-                // Digging toward target to create new breach
-                // TODO: Implement dig behavior
+                if leader.is_broken() {
+                    leader.retreat(entity, &mut breach_points);
+                    continue;
+                }
+
+                // Through - lead the way in if it opened a breach
+                if world.get_tile(target).is_none_or(|t| t.is_passable()) {
+                    let opened = breach_points.adjacent(target).map(|b| b.entity);
+                    if !opened.is_some_and(|breach| {
+                        leader.claim_breach(
+                            entity,
+                            breach,
+                            pos,
+                            &mut breach_points,
+                            &portal_graph,
+                            world,
+                        )
+                    }) {
+                        leader.seek();
+                    }
+                    continue;
+                }
+
+                if is_near(pos, target) {
+                    match attackable(target, world, &victims) {
+                        Some(victim) => hit(victim, target),
+                        // Can't be dug - find another way
+                        None => leader.seek(),
+                    }
+                    continue;
+                }
+
+                let field = goal_fields.get(GoalKey::Position(target));
+                let dir = field.flow_direction(pos);
+                if let Step::Blocked(wall) = step_along(dir, &mut transform, world, max_step) {
+                    // Dig toward the target
+                    if let Some(victim) = attackable(wall, world, &victims) {
+                        hit(victim, wall);
+                    }
+                }
             }
 
             LeaderState::Retreating => {
-                // Fall back toward nest
-                // TODO: Implement retreat behavior
+                // Fall back toward nest, and start over once there
+                let Some(home) = home else {
+                    leader.seek();
+                    continue;
+                };
+                if is_near(pos, home) {
+                    info!("Leader {:?} regrouped at nest", entity);
+                    leader.seek();
+                    continue;
+                }
+
+                let field = goal_fields.get(GoalKey::NestHome(home));
+                let dir = field.flow_direction(pos);
+                if let Step::Blocked(wall) = step_along(dir, &mut transform, world, max_step) {
+                    if let Some(victim) = attackable(wall, world, &victims) {
+                        hit(victim, wall);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// System to post reinforcement requests from leaders holding a breach
+/// with too few followers
+///
+/// Help already on its way - swarms marching to back the leader and ants
+/// assigned to it in the tunnels - counts toward the shortfall.
+pub fn request_reinforcements(
+    leaders: Query<(Entity, &SwarmLeader)>,
+    network: Res<TunnelNetwork>,
+    mut requests: EventWriter<ReinforcementRequest>,
+    mut timer: Local<f32>,
+    time: Res<Time>,
+) {
+    *timer += time.delta_secs();
+    if *timer < REQUEST_INTERVAL {
+        return;
+    }
+    *timer = 0.0;
+
+    let mut incoming: hashbrown::HashMap<Entity, u32> = hashbrown::HashMap::new();
+    for (_, leader) in leaders.iter() {
+        if let LeaderState::Reinforcing { ally } = leader.state {
+            *incoming.entry(ally).or_insert(0) += leader.follower_count.max(1);
+        }
+    }
+    let queued = network.segments.iter().filter(|s| s.intact).flat_map(|s| s.queue.iter());
+    for holder in queued.filter_map(|ant| ant.leader) {
+        *incoming.entry(holder).or_insert(0) += 1;
+    }
+
+    for (entity, leader) in leaders.iter() {
+        let Some(breach) = leader.claimed_breach else {
+            continue;
+        };
+        if leader.target().is_none() || !leader.needs_reinforcements() {
+            continue;
+        }
+        let incoming = incoming.get(&entity).copied().unwrap_or(0);
+        let shortfall = leader.shortfall().saturating_sub(incoming);
+        if shortfall > 0 {
+            requests.send(ReinforcementRequest {
+                leader: entity,
+                breach,
+                shortfall,
+            });
+        }
+    }
+}
+
+/// System to send reinforcements to breaches that asked for them
+///
+/// Requests are posted on the breach they came from. Leaderless ants
+/// still travelling through tunnels are assigned to the holder first and
+/// will emerge as its followers. Whatever is still missing comes from the
+/// nearest leader in range that can help: an idle one brings its whole
+/// swarm, a busy one hands its spare followers over. Each leader helps at
+/// most one breach per round.
+pub fn route_reinforcements(
+    mut requests: EventReader<ReinforcementRequest>,
+    mut leaders: Query<(Entity, &mut SwarmLeader, &Transform)>,
+    mut followers: Query<&mut Follower>,
+    mut breach_points: ResMut<BreachPoints>,
+    mut network: ResMut<TunnelNetwork>,
+) {
+    for request in requests.read() {
+        let Some(point) = breach_points.get_mut(request.breach) else {
+            continue;
+        };
+        // Only the holder speaks for a breach, and a repeat call tops the
+        // request up rather than adding to it
+        if point.claimed_by == Some(request.leader) {
+            let outstanding = point.reinforcement_requests;
+            point.request_reinforcements(request.shortfall.saturating_sub(outstanding));
+        }
+    }
+
+    let wanted: Vec<(Entity, Entity, IVec3)> = breach_points
+        .points
+        .iter()
+        .filter(|b| b.reinforcement_requests > 0)
+        .filter_map(|b| Some((b.entity, b.claimed_by?, b.position)))
+        .collect();
+    let mut helped: hashbrown::HashSet<Entity> = hashbrown::HashSet::new();
+
+    for (breach, holder, at) in wanted {
+        let Some(point) = breach_points.get_mut(breach) else {
+            continue;
+        };
+        for segment in network.segments.iter_mut().filter(|s| s.intact) {
+            for ant in segment.queue.iter_mut() {
+                if point.reinforcement_requests == 0 {
+                    break;
+                }
+                if ant.leader.is_none() {
+                    ant.leader = Some(holder);
+                    point.fulfil_reinforcements(1);
+                }
             }
         }
+        let outstanding = point.reinforcement_requests;
+        if outstanding == 0 {
+            continue;
+        }
+
+        let distance =
+            |transform: &Transform| (transform.translation.as_ivec3() - at).abs().max_element();
+        let Some(helper) = leaders
+            .iter()
+            .filter(|(entity, leader, transform)| {
+                *entity != holder
+                    && !helped.contains(entity)
+                    && leader.can_reinforce()
+                    && distance(transform) <= REQUEST_RANGE
+            })
+            .min_by_key(|(_, _, transform)| distance(transform))
+            .map(|(entity, _, _)| entity)
+        else {
+            continue;
+        };
+        helped.insert(helper);
+
+        let Ok((_, leader, _)) = leaders.get(helper) else {
+            continue;
+        };
+        let sent = if leader.is_idle() {
+            // Bring the whole swarm along
+            leader.follower_count.max(1)
+        } else {
+            // Hand spare followers over and keep the rest
+            let count = leader.spare_troops().min(outstanding);
+            let mut moved = 0;
+            for mut follower in followers.iter_mut() {
+                if moved == count {
+                    break;
+                }
+                if follower.leader == helper {
+                    follower.leader = holder;
+                    moved += 1;
+                }
+            }
+            if moved == 0 {
+                continue;
+            }
+            if let Ok((_, mut ally, _)) = leaders.get_mut(holder) {
+                ally.follower_count += moved;
+            }
+            moved
+        };
+
+        if let Ok((_, mut leader, _)) = leaders.get_mut(helper) {
+            if !leader.is_idle() {
+                leader.follower_count -= sent;
+                leader.release_claim(helper, &mut breach_points);
+            }
+            leader.route.clear();
+            leader.state = LeaderState::Reinforcing { ally: holder };
+        }
+        if let Some(point) = breach_points.get_mut(breach) {
+            point.fulfil_reinforcements(sent);
+        }
+        info!("Leader {:?} reinforcing {:?} with {} followers", helper, holder, sent);
+    }
+}
+
+/// System to keep leader follower counts in sync with actual followers
+pub fn update_follower_counts(
+    mut leaders: Query<(Entity, &mut SwarmLeader)>,
+    followers: Query<&Follower>,
+) {
+    let mut counts: hashbrown::HashMap<Entity, u32> = hashbrown::HashMap::new();
+    for follower in followers.iter() {
+        *counts.entry(follower.leader).or_insert(0) += 1;
+    }
+
+    for (entity, mut leader) in leaders.iter_mut() {
+        let count = counts.get(&entity).copied().unwrap_or(0);
+        if leader.follower_count != count {
+            leader.follower_count = count;
+        }
+    }
+}
+
+/// System to clear breach claims held by leaders that no longer exist
+pub fn release_orphaned_claims(
+    mut breach_points: ResMut<BreachPoints>,
+    leaders: Query<&SwarmLeader>,
+) {
+    let orphaned: Vec<Entity> = breach_points
+        .points
+        .iter()
+        .filter_map(|b| b.claimed_by)
+        .filter(|holder| leaders.get(*holder).is_err())
+        .collect();
+
+    for holder in orphaned {
+        breach_points.release_all(holder);
+        info!("Released breach claims of despawned leader {:?}", holder);
+    }
+}
+
+/// System to keep chunks around active leaders streamed in
+pub fn publish_leader_anchors(
+    leaders: Query<&Transform, With<SwarmLeader>>,
+    mut anchors: ResMut<StreamingAnchors>,
+) {
+    let tiles = leaders.iter().map(|t| t.translation.as_ivec3());
+    anchors.set(AnchorSource::Ants, tiles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::Breach;
+
+    /// Spawn a leader assaulting its own breach, with `count` followers
+    fn spawn_swarm(app: &mut App, at: IVec3, count: u32) -> Entity {
+        let breach = app.world_mut().spawn(Breach).id();
+        app.world_mut().resource_mut::<BreachPoints>().add(breach, at);
+
+        let mut leader = SwarmLeader::new(10);
+        leader.follower_count = count;
+        leader.claimed_breach = Some(breach);
+        leader.state = LeaderState::Assaulting { target: at };
+        let entity = app
+            .world_mut()
+            .spawn((leader, Transform::from_translation(at.as_vec3())))
+            .id();
+        assert!(app
+            .world_mut()
+            .resource_mut::<BreachPoints>()
+            .try_claim(breach, entity));
+        for _ in 0..count {
+            app.world_mut().spawn(Follower::new(entity, Vec2::ZERO));
+        }
+        entity
+    }
+
+    fn reinforcement_app() -> App {
+        let mut app = App::new();
+        app.add_event::<ReinforcementRequest>()
+            .init_resource::<BreachPoints>()
+            .init_resource::<TunnelNetwork>()
+            .add_systems(Update, (route_reinforcements, update_follower_counts).chain());
+        app
+    }
+
+    /// Have `leader` ask for its shortfall at the breach it holds
+    fn send_request(app: &mut App, leader: Entity) -> u32 {
+        let swarm = app.world().get::<SwarmLeader>(leader).unwrap();
+        let (breach, shortfall) = (swarm.claimed_breach.unwrap(), swarm.shortfall());
+        app.world_mut().send_event(ReinforcementRequest {
+            leader,
+            breach,
+            shortfall,
+        });
+        shortfall
+    }
+
+    #[test]
+    fn strong_swarm_reinforces_weak_one() {
+        let mut app = reinforcement_app();
+
+        let weak_breach = IVec3::new(10, 0, 0);
+        let weak = spawn_swarm(&mut app, weak_breach, 2);
+        let strong = spawn_swarm(&mut app, IVec3::new(20, 0, 0), 10);
+        {
+            let world = app.world();
+            assert!(world.get::<SwarmLeader>(weak).unwrap().needs_reinforcements());
+            assert!(world.get::<SwarmLeader>(strong).unwrap().can_spare_troops());
+        }
+
+        let shortfall = send_request(&mut app, weak);
+        app.update();
+
+        let world = app.world_mut();
+        let mut followers = world.query::<&Follower>();
+        let moved = followers
+            .iter(world)
+            .filter(|follower| follower.leader == weak)
+            .count();
+        assert_eq!(moved, 2 + shortfall as usize);
+
+        let weak_leader = world.get::<SwarmLeader>(weak).unwrap();
+        assert_eq!(weak_leader.follower_count, 2 + shortfall);
+        let strong_leader = world.get::<SwarmLeader>(strong).unwrap();
+        assert_eq!(strong_leader.follower_count, 10 - shortfall);
+        assert_eq!(strong_leader.state, LeaderState::Reinforcing { ally: weak });
+        // The helper's breach is free for someone else
+        let breach_points = world.resource::<BreachPoints>();
+        assert!(breach_points.at_position(IVec3::new(20, 0, 0)).unwrap().claimed_by.is_none());
+    }
+
+    #[test]
+    fn claimed_breach_draws_idle_leader() {
+        let mut app = reinforcement_app();
+
+        let breach_pos = IVec3::new(10, 0, 0);
+        let holder = spawn_swarm(&mut app, breach_pos, 1);
+        let idle = app
+            .world_mut()
+            .spawn((SwarmLeader::new(10), Transform::from_xyz(25.0, 0.0, 0.0)))
+            .id();
+        for _ in 0..3 {
+            app.world_mut().spawn(Follower::new(idle, Vec2::ZERO));
+        }
+        // Too far away to be asked
+        let distant = app
+            .world_mut()
+            .spawn((SwarmLeader::new(10), Transform::from_xyz(100.0, 0.0, 0.0)))
+            .id();
+        for _ in 0..3 {
+            app.world_mut().spawn(Follower::new(distant, Vec2::ZERO));
+        }
+        // Counts catch up with the followers on the first update
+        app.update();
+
+        let shortfall = send_request(&mut app, holder);
+        app.update();
+
+        let world = app.world();
+        let idle_leader = world.get::<SwarmLeader>(idle).unwrap();
+        assert_eq!(idle_leader.state, LeaderState::Reinforcing { ally: holder });
+        // It keeps its own swarm
+        assert_eq!(idle_leader.follower_count, 3);
+        assert_eq!(world.get::<SwarmLeader>(distant).unwrap().state, LeaderState::Seeking);
+        let breach_points = world.resource::<BreachPoints>();
+        let point = breach_points.at_position(breach_pos).unwrap();
+        assert_eq!(point.reinforcement_requests, shortfall - 3);
     }
 }
@@ -9,6 +9,8 @@
 //! - Biomass from kills = tech advancement
 //! - Awareness state machine drives escalation
 
+use crate::world::{EnvironmentField, GameWorld, WaterField, WorldGenConfig};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
 mod ecology;
@@ -16,6 +18,7 @@ mod follower;
 mod leader;
 mod scent;
 mod scout;
+mod succession;
 mod tunnel_queue;
 
 pub use ecology::*;
@@ -23,6 +26,7 @@ pub use follower::*;
 pub use leader::*;
 pub use scent::*;
 pub use scout::*;
+pub use succession::*;
 pub use tunnel_queue::*;
 
 pub struct AiPlugin;
@@ -33,13 +37,25 @@ impl Plugin for AiPlugin {
             .init_resource::<ScentTrails>()
             .add_event::<ScoutReturnedEvent>()
             .add_event::<AwarenessChangedEvent>()
+            .add_event::<ReinforcementRequest>()
+            .add_systems(Startup, spawn_generated_nests.after(crate::world::setup_world))
             .add_systems(Update, (
-                update_leaders,
+                spawn_generated_nests.after(crate::world::stream_chunks),
+                (
+                    release_orphaned_claims,
+                    succeed_fallen_leaders,
+                    update_follower_counts,
+                    update_leaders,
+                    request_reinforcements,
+                    route_reinforcements,
+                ).chain(),
                 update_followers,
                 update_scouts,
+                scout_surroundings,
                 update_tunnel_queues,
                 update_ecology,
                 update_scent_trails,
+                publish_leader_anchors.before(crate::world::stream_chunks),
             ));
     }
 }
@@ -86,6 +102,33 @@ impl AntCaste {
         }
     }
 
+    /// Heat (0-1) the caste can take before it slows down
+    pub fn heat_tolerance(&self) -> f32 {
+        match self {
+            AntCaste::Minor => 0.4,
+            AntCaste::Median => 0.5,
+            AntCaste::Major => 0.7,
+            AntCaste::Scout => 0.3,
+            AntCaste::Siege => 0.8,
+        }
+    }
+
+    /// Speed multiplier in the given heat
+    pub fn heat_factor(&self, heat: f32) -> f32 {
+        (1.0 - 1.5 * (heat - self.heat_tolerance()).max(0.0)).max(0.25)
+    }
+
+    /// Preference when promoting a follower to leader (None = can't lead)
+    pub fn leadership(&self) -> Option<u8> {
+        match self {
+            AntCaste::Major => Some(3),
+            AntCaste::Siege => Some(2),
+            AntCaste::Median => Some(1),
+            AntCaste::Minor => Some(0),
+            AntCaste::Scout => None,
+        }
+    }
+
     /// Biomass cost to produce
     pub fn biomass_cost(&self) -> u32 {
         match self {
@@ -118,3 +161,21 @@ impl Ant {
         }
     }
 }
+
+/// The ground an ant moves over: the world plus the heat and water that
+/// slow it down
+#[derive(SystemParam)]
+pub struct Footing<'w> {
+    pub world: Res<'w, GameWorld>,
+    water: Res<'w, WaterField>,
+    environment: Res<'w, EnvironmentField>,
+    config: Res<'w, WorldGenConfig>,
+}
+
+impl Footing<'_> {
+    /// Speed multiplier for a caste standing at `pos`
+    pub fn speed(&self, caste: AntCaste, pos: IVec3) -> f32 {
+        let heat = self.environment.conditions(&self.config, &self.world, pos).heat;
+        caste.move_speed() * caste.heat_factor(heat) * self.water.movement_factor(pos)
+    }
+}
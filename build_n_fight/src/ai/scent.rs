@@ -55,6 +55,18 @@ impl ScentTrails {
             .copied()
     }
 
+    /// Iterate every (position, nest, intensity) entry
+    pub fn entries(&self) -> impl Iterator<Item = (IVec3, Entity, f32)> + '_ {
+        self.trails.iter().flat_map(|(pos, nests)| {
+            nests.iter().map(move |(nest, intensity)| (*pos, *nest, *intensity))
+        })
+    }
+
+    /// Remove all scent
+    pub fn clear(&mut self) {
+        self.trails.clear();
+    }
+
     /// Decay all scents
     pub fn decay(&mut self, decay_rate: f32) {
         for pos_scents in self.trails.values_mut() {
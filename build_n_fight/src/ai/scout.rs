@@ -5,8 +5,13 @@
 //! Kill scouts before they report!
 
 use super::*;
+use crate::flow::{GoalFields, GoalKey};
+use crate::world::{GameWorld, Region};
 use bevy::prelude::*;
 
+/// How far scouts can spot resource deposits
+const SCOUT_SIGHT: i32 = 2;
+
 /// Scout-specific component
 #[derive(Component)]
 pub struct Scout {
@@ -48,6 +53,13 @@ impl Scout {
         self.discoveries.push(discovery);
     }
 
+    /// Has this resource deposit already been noted?
+    fn knows_resource(&self, pos: IVec3) -> bool {
+        self.discoveries
+            .iter()
+            .any(|d| matches!(d, Discovery::Resource { position, .. } if *position == pos))
+    }
+
     /// Check if scout has important intel
     pub fn has_important_intel(&self) -> bool {
         self.discoveries.iter().any(|d| {
@@ -63,45 +75,79 @@ pub struct ScoutReturnedEvent {
     pub discoveries: Vec<Discovery>,
 }
 
-/// System to update scout behavior
+/// System to walk returning scouts home and report what they found
 pub fn update_scouts(
     mut commands: Commands,
-    mut scouts: Query<(Entity, &mut Scout, &Transform)>,
+    mut scouts: Query<(Entity, &Scout, &mut Transform), Without<AntNest>>,
     nests: Query<&Transform, With<AntNest>>,
     mut scout_events: EventWriter<ScoutReturnedEvent>,
-    mut scent_trails: ResMut<ScentTrails>,
+    mut goal_fields: GoalFields,
+    footing: Footing,
+    time: Res<Time>,
 ) {
-    for (entity, mut scout, transform) in scouts.iter_mut() {
-        let pos = transform.translation.truncate().as_ivec2().extend(0);
-
-        if scout.returning {
-            // Moving back home
-            if let Ok(nest_transform) = nests.get(scout.origin_nest) {
-                let nest_pos = nest_transform.translation.truncate().as_ivec2();
-                let dist = (nest_pos - pos.truncate()).abs();
+    for (entity, scout, mut transform) in scouts.iter_mut() {
+        if !scout.returning {
+            continue;
+        }
+        let Ok(nest_transform) = nests.get(scout.origin_nest) else {
+            continue;
+        };
+        let pos = transform.translation.as_ivec3();
 
-                if dist.x <= 1 && dist.y <= 1 {
-                    // Reached home! Report discoveries
-                    if scout.has_important_intel() {
-                        scout_events.send(ScoutReturnedEvent {
-                            nest: scout.origin_nest,
-                            discoveries: scout.discoveries.clone(),
-                        });
-                        info!("Scout returned with {} discoveries!", scout.discoveries.len());
-                    }
-                    // Despawn scout (will be recycled into nest population)
-                    commands.entity(entity).despawn();
-                }
+        let nest_pos = nest_transform.translation.truncate().as_ivec2();
+        let dist = (nest_pos - pos.truncate()).abs();
+        if dist.x <= 1 && dist.y <= 1 {
+            // Reached home! Report discoveries
+            if scout.has_important_intel() {
+                scout_events.send(ScoutReturnedEvent {
+                    nest: scout.origin_nest,
+                    discoveries: scout.discoveries.clone(),
+                });
+                info!("Scout returned with {} discoveries!", scout.discoveries.len());
             }
+            // Despawn scout (will be recycled into nest population)
+            commands.entity(entity).despawn();
         } else {
-            // Exploring - leave scent trail
-            scent_trails.add_scent(pos, scout.origin_nest, 1.0);
-            scout.home_path.push(pos);
+            // Follow the nest's own flow field home
+            let home = GoalKey::NestHome(nest_transform.translation.as_ivec3());
+            if let Some(dir) = goal_fields.get(home).flow_direction(pos) {
+                let step = (pos + dir).as_vec3() - transform.translation;
+                let max_step = footing.speed(AntCaste::Scout, pos) * time.delta_secs();
+                transform.translation += step.clamp_length_max(max_step);
+            }
+        }
+    }
+}
 
-            // TODO: Exploration logic
-            // - Move toward unexplored areas
-            // - Check for player presence
-            // - Avoid dangers
+/// System to have exploring scouts mark their trail and note what they see
+pub fn scout_surroundings(
+    mut scouts: Query<(&mut Scout, &Transform)>,
+    mut scent_trails: ResMut<ScentTrails>,
+    world: Res<GameWorld>,
+) {
+    for (mut scout, transform) in scouts.iter_mut() {
+        if scout.returning {
+            continue;
         }
+        let pos = transform.translation.as_ivec3();
+
+        // Leave scent trail
+        scent_trails.add_scent(pos, scout.origin_nest, 1.0);
+        scout.home_path.push(pos);
+
+        // Note any deposits in sight
+        for (seen, tile) in world.tiles_in(Region::sphere(pos, SCOUT_SIGHT)) {
+            if !tile.def().yields.is_empty() && !scout.knows_resource(seen) {
+                scout.discover(Discovery::Resource {
+                    position: seen,
+                    resource_type: tile.def().name.clone(),
+                });
+            }
+        }
+
+        // TODO: Exploration logic
+        // - Move toward unexplored areas
+        // - Check for player presence
+        // - Avoid dangers
     }
 }
@@ -0,0 +1,204 @@
+//! Leader succession - what a swarm does when its leader dies
+//!
+//! Stranded followers are regrouped once a second. A big enough swarm
+//! promotes its best ant to leader; smaller ones (or swarms with no ant
+//! fit to lead) join the nearest leaders with room. Anyone left over keeps
+//! following the flow field (see `update_followers`) and tries again.
+
+use super::*;
+use crate::world::SecondTick;
+
+/// Stranded swarms at least this big promote one of their own
+const MIN_SUCCESSION_SWARM: usize = 4;
+/// How far (in tiles) stranded followers look for a leader to join
+const JOIN_RANGE: i32 = 30;
+
+/// System to find new leaders for followers whose leader is gone
+pub fn succeed_fallen_leaders(
+    mut commands: Commands,
+    mut followers: Query<(Entity, &mut Follower, &Ant, &Transform)>,
+    mut leaders: Query<(Entity, &mut SwarmLeader, &Transform), Without<Follower>>,
+    tick: Res<SecondTick>,
+) {
+    if !tick.fired() {
+        return;
+    }
+
+    let mut stranded: hashbrown::HashMap<Entity, Vec<Entity>> = hashbrown::HashMap::new();
+    for (entity, follower, _, _) in followers.iter() {
+        if leaders.get(follower.leader).is_err() {
+            stranded.entry(follower.leader).or_default().push(entity);
+        }
+    }
+
+    for (fallen, mut swarm) in stranded {
+        // Promote the best ant, strongest first within a caste
+        let heir = swarm
+            .iter()
+            .filter_map(|entity| followers.get(*entity).ok())
+            .filter_map(|(entity, _, ant, _)| {
+                ant.caste.leadership().map(|rank| (entity, (rank, ant.hp)))
+            })
+            .max_by_key(|(_, key)| *key)
+            .map(|(entity, _)| entity);
+
+        if let Some(heir) = heir.filter(|_| swarm.len() >= MIN_SUCCESSION_SWARM) {
+            swarm.retain(|entity| *entity != heir);
+            for entity in &swarm {
+                if let Ok((_, mut follower, _, _)) = followers.get_mut(*entity) {
+                    follower.leader = heir;
+                }
+            }
+
+            // Starts at half strength: fit to fight, not to lend troops
+            let count = swarm.len() as u32;
+            let mut leader = SwarmLeader::new(count * 2);
+            leader.follower_count = count;
+            commands.entity(heir).remove::<Follower>().insert(leader);
+
+            info!(
+                "{:?} took over {} followers of fallen leader {:?}",
+                heir, count, fallen
+            );
+            continue;
+        }
+
+        // Join the nearest leaders with room
+        let center = swarm
+            .iter()
+            .filter_map(|entity| followers.get(*entity).ok())
+            .map(|(_, _, _, transform)| transform.translation)
+            .sum::<Vec3>()
+            / swarm.len() as f32;
+        let center = center.as_ivec3();
+
+        let mut allies: Vec<(Entity, i32)> = leaders
+            .iter()
+            .filter(|(_, leader, _)| leader.follower_count < leader.max_followers)
+            .map(|(entity, _, transform)| {
+                let dist = (transform.translation.as_ivec3() - center)
+                    .abs()
+                    .max_element();
+                (entity, dist)
+            })
+            .filter(|(_, dist)| *dist <= JOIN_RANGE)
+            .collect();
+        allies.sort_by_key(|(_, dist)| *dist);
+
+        let mut joined = 0;
+        for (ally, _) in allies {
+            let Ok((_, mut leader, _)) = leaders.get_mut(ally) else {
+                continue;
+            };
+            let room = (leader.max_followers - leader.follower_count) as usize;
+            let take = room.min(swarm.len() - joined);
+            for entity in &swarm[joined..joined + take] {
+                if let Ok((_, mut follower, _, _)) = followers.get_mut(*entity) {
+                    follower.leader = ally;
+                }
+            }
+            leader.follower_count += take as u32;
+            joined += take;
+            if joined == swarm.len() {
+                break;
+            }
+        }
+
+        if joined > 0 {
+            info!(
+                "{} of {} followers of fallen leader {:?} joined other swarms",
+                joined,
+                swarm.len(),
+                fallen
+            );
+        }
+        // The rest follow the flow field until a leader turns up
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::advance_second_tick;
+    use std::time::Duration;
+
+    fn succession_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<SecondTick>()
+            .add_systems(Update, (advance_second_tick, succeed_fallen_leaders).chain());
+        app
+    }
+
+    /// Spawn followers of a leader that has just died
+    fn orphaned_swarm(app: &mut App, castes: &[AntCaste], at: Vec3) -> Vec<Entity> {
+        let fallen = app.world_mut().spawn_empty().id();
+        let swarm = castes
+            .iter()
+            .map(|caste| {
+                let ant = Ant::new(*caste, Entity::PLACEHOLDER);
+                let follower = Follower::new(fallen, Vec2::ZERO);
+                app.world_mut()
+                    .spawn((ant, follower, Transform::from_translation(at)))
+                    .id()
+            })
+            .collect();
+        app.world_mut().despawn(fallen);
+        swarm
+    }
+
+    /// Run until the succession check has fired
+    fn tick(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+    }
+
+    #[test]
+    fn big_swarm_promotes_its_best_ant() {
+        let mut app = succession_app();
+        let castes = [AntCaste::Minor, AntCaste::Major, AntCaste::Median, AntCaste::Scout];
+        let swarm = orphaned_swarm(&mut app, &castes, Vec3::ZERO);
+        tick(&mut app);
+
+        let heir = swarm[1];
+        let world = app.world();
+        let leader = world.get::<SwarmLeader>(heir).expect("the major takes over");
+        assert_eq!(leader.follower_count, 3);
+        assert!(world.get::<Follower>(heir).is_none());
+        for entity in swarm.iter().filter(|entity| **entity != heir) {
+            assert_eq!(world.get::<Follower>(*entity).unwrap().leader, heir);
+        }
+    }
+
+    #[test]
+    fn small_swarm_joins_the_nearest_leader_with_room() {
+        let mut app = succession_app();
+        let spawn_leader = |app: &mut App, x: f32, room: u32| {
+            let mut leader = SwarmLeader::new(10);
+            leader.follower_count = leader.max_followers - room;
+            let at = Transform::from_xyz(x, 0.0, 0.0);
+            app.world_mut().spawn((leader, at)).id()
+        };
+        let full = spawn_leader(&mut app, 2.0, 0);
+        let near = spawn_leader(&mut app, 5.0, 1);
+        let far = spawn_leader(&mut app, 10.0, 5);
+        let castes = [AntCaste::Minor, AntCaste::Median];
+        let swarm = orphaned_swarm(&mut app, &castes, Vec3::ZERO);
+
+        // Nothing happens between ticks
+        app.update();
+        assert_ne!(app.world().get::<Follower>(swarm[0]).unwrap().leader, near);
+
+        tick(&mut app);
+        let world = app.world();
+        let leaders: Vec<Entity> = swarm
+            .iter()
+            .map(|entity| world.get::<Follower>(*entity).unwrap().leader)
+            .collect();
+        assert_eq!(leaders, [near, far]);
+        let count = |leader| world.get::<SwarmLeader>(leader).unwrap().follower_count;
+        assert_eq!((count(full), count(near), count(far)), (10, 10, 6));
+    }
+}
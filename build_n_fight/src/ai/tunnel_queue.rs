@@ -84,17 +84,21 @@ impl TunnelSegment {
         emerged
     }
 
+    /// World position at a progress point along the segment
+    pub fn point_at(&self, t: f32) -> IVec3 {
+        IVec3::new(
+            ((1.0 - t) * self.start.x as f32 + t * self.end.x as f32) as i32,
+            ((1.0 - t) * self.start.y as f32 + t * self.end.y as f32) as i32,
+            ((1.0 - t) * self.start.z as f32 + t * self.end.z as f32) as i32,
+        )
+    }
+
     /// Break the tunnel at a progress point, ejecting ants before that point
     pub fn break_at(&mut self, break_progress: f32) -> (IVec3, Vec<QueuedAnt>) {
         self.intact = false;
 
         // Calculate world position of break
-        let t = break_progress;
-        let break_pos = IVec3::new(
-            ((1.0 - t) * self.start.x as f32 + t * self.end.x as f32) as i32,
-            ((1.0 - t) * self.start.y as f32 + t * self.end.y as f32) as i32,
-            ((1.0 - t) * self.start.z as f32 + t * self.end.z as f32) as i32,
-        );
+        let break_pos = self.point_at(break_progress);
 
         // Ants before the break point get ejected here (SURPRISE!)
         let mut ejected = Vec::new();
@@ -152,11 +156,14 @@ pub fn update_tunnel_queues(
 
         // Spawn emerged ants as real entities at the end position
         for ant in emerged {
-            commands.spawn((
+            let mut entity = commands.spawn((
                 Ant::new(ant.caste, ant.nest),
                 Transform::from_translation(segment.end.as_vec3()),
-                // Will get Follower component if they have a leader
             ));
+            // Ants routed to a leader join its swarm
+            if let Some(leader) = ant.leader {
+                entity.insert(Follower::new(leader, Vec2::ZERO));
+            }
         }
     }
 }
@@ -4,7 +4,7 @@
 //! Tracks deaths for biomass rewards.
 
 use crate::ai::{Ant, AntNest, biomass_rewards};
-use crate::world::{GameWorld, Tile, TileChangedEvent};
+use crate::world::{ChangeCause, GameWorld, Tile, TileChangedEvent};
 use bevy::prelude::*;
 
 /// Damage event
@@ -65,13 +65,21 @@ pub fn process_damage(
     mut death_events: EventWriter<DeathEvent>,
     mut tile_events: EventWriter<TileChangedEvent>,
     mut world: ResMut<GameWorld>,
-    mut health_query: Query<(&mut Health, &Transform, Option<&Ant>)>,
+    mut health_query: Query<(Option<&mut Health>, &Transform, Option<&mut Ant>)>,
 ) {
     for event in damage_events.read() {
         match &event.target {
             DamageTarget::Entity(entity) => {
-                if let Ok((mut health, transform, ant)) = health_query.get_mut(*entity) {
-                    let died = health.damage(event.amount);
+                if let Ok((health, transform, mut ant)) = health_query.get_mut(*entity) {
+                    // Ants carry their own HP instead of a Health component
+                    let died = match (health, ant.as_deref_mut()) {
+                        (Some(mut health), _) => health.damage(event.amount),
+                        (None, Some(ant)) => {
+                            ant.hp = ant.hp.saturating_sub(event.amount.ceil() as u16);
+                            ant.hp == 0
+                        }
+                        (None, None) => false,
+                    };
 
                     if died {
                         // Calculate biomass value
@@ -104,13 +112,17 @@ pub fn process_damage(
                             position: *pos,
                             old_tile,
                             new_tile: Tile::Rubble,
+                            cause: ChangeCause::Damage,
+                            actor: event.source,
                         });
-                    } else {
+                    } else if tile != old_tile {
                         // Tile damaged but not destroyed
                         tile_events.send(TileChangedEvent {
                             position: *pos,
                             old_tile,
                             new_tile: tile,
+                            cause: ChangeCause::Damage,
+                            actor: event.source,
                         });
                     }
 
@@ -2,7 +2,12 @@
 //!
 //! Projectiles travel from weapon to target.
 //! Some are instant (hitscan), some have travel time.
+//! On landing they send a `DamageEvent` for every tile and unit in the
+//! blast, sourced from the weapon so its effects (like fire) can follow.
 
+use super::{DamageEvent, DamageTarget, Health};
+use crate::ai::Ant;
+use crate::world::{GameWorld, Region};
 use bevy::prelude::*;
 
 /// Projectile component
@@ -12,7 +17,7 @@ pub struct Projectile {
     pub speed: f32,
     pub target: IVec3,
     pub aoe_radius: Option<f32>,
-    /// Who fired this (for friendly fire prevention)
+    /// Weapon that fired this (for friendly fire prevention)
     pub source: Entity,
 }
 
@@ -31,14 +36,30 @@ impl Projectile {
         self.aoe_radius = Some(radius);
         self
     }
+
+    /// Tiles hit on landing
+    pub fn blast(&self) -> Region {
+        let radius = self.aoe_radius.map_or(0, |radius| radius.round() as i32);
+        Region::sphere(self.target, radius)
+    }
 }
 
+/// Units and ants a landing projectile can hit
+type Targets<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Transform),
+    (Or<(With<Health>, With<Ant>)>, Without<Projectile>),
+>;
+
 /// System to update projectile movement
 pub fn update_projectiles(
     mut commands: Commands,
     mut projectiles: Query<(Entity, &mut Transform, &Projectile)>,
+    victims: Targets,
+    mut damage_events: EventWriter<DamageEvent>,
+    world: Res<GameWorld>,
     time: Res<Time>,
-    // TODO: Add damage event writer
 ) {
     let dt = time.delta_secs();
 
@@ -50,12 +71,28 @@ pub fn update_projectiles(
 
         if distance < 1.0 {
             // Reached target - deal damage and despawn
-            // TODO: Send damage event
+            let blast = projectile.blast();
+            let hit = |target, position| DamageEvent {
+                target,
+                amount: projectile.damage,
+                source: Some(projectile.source),
+                position,
+            };
+            damage_events.send_batch(
+                blast
+                    .positions()
+                    .filter(|pos| world.get_tile(*pos).is_some())
+                    .map(|pos| hit(DamageTarget::Tile(pos), pos)),
+            );
+            damage_events.send_batch(victims.iter().filter_map(|(victim, transform)| {
+                let pos = transform.translation.as_ivec3();
+                blast.contains(pos).then(|| hit(DamageTarget::Entity(victim), pos))
+            }));
             commands.entity(entity).despawn();
         } else {
-            // Move toward target
+            // Move toward target, without overshooting it
             let movement = direction.normalize() * projectile.speed * dt;
-            transform.translation += movement;
+            transform.translation += movement.clamp_length_max(distance);
         }
     }
 }
@@ -3,6 +3,8 @@
 //! Weapons are data-driven and mountable.
 //! Each weapon type has different targeting capabilities.
 
+use super::Projectile;
+use crate::ai::Ant;
 use bevy::prelude::*;
 
 /// Weapon categories
@@ -156,6 +158,22 @@ impl Weapon {
         self.current_ammo = (self.current_ammo + amount).min(self.ammo_capacity);
     }
 
+    /// Does this weapon set what it hits on fire?
+    pub fn ignites(&self) -> bool {
+        self.category == WeaponCategory::Flamer
+    }
+
+    /// Tiles per second its shots travel
+    pub fn projectile_speed(&self) -> f32 {
+        match self.category {
+            WeaponCategory::Ballista => 20.0,
+            WeaponCategory::Mortar => 8.0,
+            WeaponCategory::Flamer => 30.0,
+            WeaponCategory::BombDrop => 10.0,
+            WeaponCategory::Crossbow => 25.0,
+        }
+    }
+
     /// Can this weapon target a position relative to itself?
     pub fn can_target(&self, relative_pos: IVec3) -> bool {
         let dz = relative_pos.z;
@@ -175,16 +193,43 @@ impl Weapon {
 }
 
 /// System to update weapons (cooldowns, auto-targeting)
+///
+/// Each weapon fires at the nearest ant it can reach, launching a
+/// `Projectile` that deals the damage when it lands.
 pub fn update_weapons(
-    mut weapons: Query<(&mut Weapon, &Transform)>,
+    mut commands: Commands,
+    mut weapons: Query<(Entity, &mut Weapon, &GlobalTransform)>,
+    ants: Query<&Transform, With<Ant>>,
     time: Res<Time>,
-    // TODO: Add targeting queries
 ) {
     let dt = time.delta_secs();
 
-    for (mut weapon, _transform) in weapons.iter_mut() {
+    for (entity, mut weapon, transform) in weapons.iter_mut() {
         weapon.update(dt);
+        if !weapon.can_fire() {
+            continue;
+        }
 
-        // TODO: Auto-targeting based on weapon type and available targets
+        let from = transform.translation();
+        let target = ants
+            .iter()
+            .map(|ant| ant.translation)
+            .filter(|pos| {
+                from.distance(*pos) <= weapon.range
+                    && weapon.can_target(pos.as_ivec3() - from.as_ivec3())
+            })
+            .min_by(|a, b| from.distance_squared(*a).total_cmp(&from.distance_squared(*b)));
+        let Some(target) = target else {
+            continue;
+        };
+
+        if weapon.fire() {
+            let speed = weapon.projectile_speed();
+            let mut projectile = Projectile::new(weapon.damage, speed, target.as_ivec3(), entity);
+            if let Some(radius) = weapon.aoe_radius {
+                projectile = projectile.with_aoe(radius);
+            }
+            commands.spawn((projectile, Transform::from_translation(from)));
+        }
     }
 }
@@ -2,7 +2,14 @@
 //!
 //! When a wall is destroyed, it becomes a breach point.
 //! Swarm leaders can claim breach points to coordinate attacks.
+//! Rebuilding over the rubble seals the breach again.
+//!
+//! Each breach is backed by an entity, so claims keep pointing at the same
+//! breach when it widens: a defense lost right next to an open breach is
+//! folded into it rather than opening a new one.
 
+use super::NEIGHBORS;
+use crate::world::{ChangeCause, Enclosure, GameWorld, Tile, TileChanges};
 use bevy::prelude::*;
 
 /// Collection of active breach points
@@ -11,9 +18,15 @@ pub struct BreachPoints {
     pub points: Vec<BreachPoint>,
 }
 
+/// Marker for the entity backing a breach point
+#[derive(Component)]
+pub struct Breach;
+
 /// A breach in the player's defenses
 #[derive(Debug, Clone)]
 pub struct BreachPoint {
+    /// Entity backing this breach while it stays open
+    pub entity: Entity,
     /// Position of the breach
     pub position: IVec3,
     /// Swarm leader that claimed this breach (if any)
@@ -25,8 +38,9 @@ pub struct BreachPoint {
 }
 
 impl BreachPoint {
-    pub fn new(position: IVec3) -> Self {
+    pub fn new(entity: Entity, position: IVec3) -> Self {
         Self {
+            entity,
             position,
             claimed_by: None,
             reinforcement_requests: 0,
@@ -44,10 +58,23 @@ impl BreachPoint {
         self.claimed_by = Some(leader);
     }
 
+    /// Release the claim if held by this leader
+    pub fn release(&mut self, leader: Entity) {
+        if self.claimed_by == Some(leader) {
+            self.claimed_by = None;
+            self.reinforcement_requests = 0;
+        }
+    }
+
     /// Request reinforcements at this breach
     pub fn request_reinforcements(&mut self, count: u32) {
         self.reinforcement_requests += count;
     }
+
+    /// Mark some requested reinforcements as dispatched
+    pub fn fulfil_reinforcements(&mut self, count: u32) {
+        self.reinforcement_requests = self.reinforcement_requests.saturating_sub(count);
+    }
 }
 
 impl BreachPoints {
@@ -62,6 +89,23 @@ impl BreachPoints {
             })
     }
 
+    /// Find breach by its entity
+    pub fn get(&self, breach: Entity) -> Option<&BreachPoint> {
+        self.points.iter().find(|b| b.entity == breach)
+    }
+
+    /// Find breach by its entity (mutable)
+    pub fn get_mut(&mut self, breach: Entity) -> Option<&mut BreachPoint> {
+        self.points.iter_mut().find(|b| b.entity == breach)
+    }
+
+    /// Find a breach at or right next to a position
+    pub fn adjacent(&self, pos: IVec3) -> Option<&BreachPoint> {
+        self.points
+            .iter()
+            .find(|b| (b.position - pos).abs().max_element() <= 1)
+    }
+
     /// Find breach by position
     pub fn at_position(&self, pos: IVec3) -> Option<&BreachPoint> {
         self.points.iter().find(|b| b.position == pos)
@@ -72,30 +116,129 @@ impl BreachPoints {
         self.points.iter_mut().find(|b| b.position == pos)
     }
 
-    /// Add a new breach point
-    pub fn add(&mut self, position: IVec3) {
+    /// Add a new breach point backed by an entity
+    pub fn add(&mut self, entity: Entity, position: IVec3) {
         if self.at_position(position).is_none() {
-            self.points.push(BreachPoint::new(position));
+            self.points.push(BreachPoint::new(entity, position));
+        }
+    }
+
+    /// Claim a breach if it is free (or already ours)
+    pub fn try_claim(&mut self, breach: Entity, leader: Entity) -> bool {
+        match self.get_mut(breach) {
+            Some(breach) if breach.claimed_by.is_none_or(|holder| holder == leader) => {
+                breach.claim(leader);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Release every claim held by a leader
+    pub fn release_all(&mut self, leader: Entity) {
+        for breach in &mut self.points {
+            breach.release(leader);
         }
     }
+
+    /// Remove a breach point, returning it if it existed
+    pub fn remove(&mut self, position: IVec3) -> Option<BreachPoint> {
+        let index = self.points.iter().position(|b| b.position == position)?;
+        Some(self.points.remove(index))
+    }
 }
 
 /// Event fired when a new breach is created
 #[derive(Event)]
 pub struct BreachCreatedEvent {
     pub position: IVec3,
+    /// What brought the defense down
+    pub cause: ChangeCause,
+    pub actor: Option<Entity>,
+}
+
+/// Event fired when a breach is sealed (rebuilt over)
+#[derive(Event)]
+pub struct BreachClosedEvent {
+    pub position: IVec3,
+}
+
+/// Did opening this tile connect the inside of the base to the outside?
+///
+/// Uses the enclosure map from *before* the change: the destroyed tile
+/// must have bordered the inside, and must now open onto the outside or
+/// onto tunnels below the surface.
+pub fn opens_enclosure(world: &GameWorld, enclosure: &Enclosure, pos: IVec3) -> bool {
+    let borders_inside = enclosure.is_inside(pos)
+        || NEIGHBORS.iter().any(|offset| enclosure.is_inside(pos + *offset));
+    let opens_out = NEIGHBORS.iter().map(|offset| pos + *offset).any(|next| {
+        world.get_tile(next).is_some_and(|t| t.is_passable())
+            && !enclosure.is_inside(next)
+            && (enclosure.is_outside(next) || next.z < world.surface_z)
+    });
+
+    borders_inside && opens_out
+}
+
+/// System to detect breaches opened and sealed by tile changes
+pub fn detect_breaches(
+    world: Res<GameWorld>,
+    enclosure: Res<Enclosure>,
+    breach_points: Res<BreachPoints>,
+    mut tile_events: TileChanges,
+    mut created_events: EventWriter<BreachCreatedEvent>,
+    mut closed_events: EventWriter<BreachClosedEvent>,
+) {
+    for event in tile_events.read() {
+        let was_defense = matches!(event.old_tile, Tile::Wall { .. } | Tile::Floor { .. });
+
+        // Collapsed defenses leave air behind, destroyed ones rubble
+        if was_defense && event.new_tile.is_passable() {
+            if opens_enclosure(&world, &enclosure, event.position) {
+                created_events.send(BreachCreatedEvent {
+                    position: event.position,
+                    cause: event.cause,
+                    actor: event.actor,
+                });
+            }
+        } else if !event.new_tile.is_passable()
+            && breach_points.at_position(event.position).is_some()
+        {
+            closed_events.send(BreachClosedEvent {
+                position: event.position,
+            });
+        }
+    }
 }
 
 /// System to manage breach points
 pub fn manage_breach_points(
+    mut commands: Commands,
     mut breach_points: ResMut<BreachPoints>,
     mut breach_events: EventReader<BreachCreatedEvent>,
+    mut closed_events: EventReader<BreachClosedEvent>,
     time: Res<Time>,
 ) {
-    // Add new breaches from events
+    // Add new breaches from events, widening any right next to them
     for event in breach_events.read() {
-        breach_points.add(event.position);
-        info!("Breach created at {:?}", event.position);
+        if let Some(breach) = breach_points.adjacent(event.position) {
+            info!("Breach at {:?} widened to {:?}", breach.position, event.position);
+            continue;
+        }
+        let entity = commands.spawn(Breach).id();
+        breach_points.add(entity, event.position);
+        info!(
+            "Breach created at {:?} ({:?} by {:?})",
+            event.position, event.cause, event.actor
+        );
+    }
+
+    // Drop breaches that were rebuilt over
+    for event in closed_events.read() {
+        if let Some(breach) = breach_points.remove(event.position) {
+            commands.entity(breach.entity).despawn();
+            info!("Breach sealed at {:?}", event.position);
+        }
     }
 
     // Update age of all breaches
@@ -0,0 +1,266 @@
+//! Per-goal flow field cache
+//!
+//! The global `TraversalField` flows toward every goal at once, and is what
+//! anything headed for the player base follows. Leaders heading to a
+//! specific breach, or scouts heading home, need their own field. Fields
+//! are built lazily on first use, repaired chunk by chunk as the traversal
+//! costs change, and evicted least-recently-used. Route waypoints get small
+//! fields covering just the chunk a leader is in and the waypoint's chunk.
+
+use super::{dijkstra, repair_region, BreachPoints, TraversalField};
+use crate::world::{chunk_position, Region};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Default number of goal fields kept alive
+pub const DEFAULT_CACHE_CAPACITY: usize = 16;
+
+/// What a cached flow field leads toward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GoalKey {
+    /// A specific breach point
+    Breach(IVec3),
+    /// A nest's home position
+    NestHome(IVec3),
+    /// Any single tile (e.g. an assault target)
+    Position(IVec3),
+    /// A portal tile on a coarse route, reached from within chunk `from`
+    Waypoint { tile: IVec3, from: IVec3 },
+}
+
+impl GoalKey {
+    /// The tile the field leads to
+    fn tile(&self) -> IVec3 {
+        match *self {
+            GoalKey::Breach(pos)
+            | GoalKey::NestHome(pos)
+            | GoalKey::Position(pos)
+            | GoalKey::Waypoint { tile: pos, .. } => pos,
+        }
+    }
+}
+
+/// Flow field toward a single goal
+#[derive(Default)]
+pub struct GoalField {
+    pub distances: hashbrown::HashMap<IVec3, u32>,
+    pub flow_directions: hashbrown::HashMap<IVec3, IVec3>,
+    /// `TraversalField::generation` this field was built from
+    generation: u64,
+    /// Cache clock value at last access
+    last_used: u64,
+}
+
+impl GoalField {
+    /// Get the flow direction from a position toward the goal
+    pub fn flow_direction(&self, pos: IVec3) -> Option<IVec3> {
+        self.flow_directions.get(&pos).copied()
+    }
+
+    /// Get accumulated cost to the goal (u32::MAX if unreachable)
+    pub fn distance(&self, pos: IVec3) -> u32 {
+        *self.distances.get(&pos).unwrap_or(&u32::MAX)
+    }
+
+    /// Catch up with the traversal costs by repairing only the chunks that
+    /// changed. Fails if the changes go back too far to know.
+    fn repair(&mut self, goal: IVec3, traversal: &TraversalField) -> bool {
+        let Some(chunks) = traversal.chunks_changed_since(self.generation) else {
+            return false;
+        };
+        let invalid = chunks
+            .into_iter()
+            .flat_map(|chunk| Region::chunk(chunk).positions())
+            .collect();
+        repair_region(
+            &traversal.costs,
+            &mut self.distances,
+            &mut self.flow_directions,
+            invalid,
+            std::iter::once(goal),
+        );
+        self.generation = traversal.generation;
+        true
+    }
+}
+
+/// LRU cache of goal-specific flow fields
+#[derive(Resource)]
+pub struct FlowFieldCache {
+    fields: hashbrown::HashMap<GoalKey, GoalField>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl Default for FlowFieldCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl FlowFieldCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            fields: hashbrown::HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    /// Get the field for a goal, building or repairing it if needed
+    pub fn get_or_build(&mut self, key: GoalKey, traversal: &TraversalField) -> &GoalField {
+        self.clock += 1;
+        let clock = self.clock;
+
+        // Waypoint fields are small enough to just rebuild
+        let fresh = match self.fields.get_mut(&key) {
+            Some(field) if field.generation == traversal.generation => true,
+            Some(_) if matches!(key, GoalKey::Waypoint { .. }) => false,
+            Some(field) => field.repair(key.tile(), traversal),
+            None => false,
+        };
+
+        if !fresh {
+            if !self.fields.contains_key(&key) && self.fields.len() >= self.capacity {
+                self.evict_lru();
+            }
+
+            // Waypoint fields only cover the two chunks either side
+            let local_costs;
+            let costs = match key {
+                GoalKey::Waypoint { tile, from } => {
+                    local_costs = chunk_costs(traversal, &[from, chunk_position(tile)]);
+                    &local_costs
+                }
+                _ => &traversal.costs,
+            };
+
+            let mut field = GoalField {
+                generation: traversal.generation,
+                ..default()
+            };
+            dijkstra(
+                costs,
+                &mut field.distances,
+                &mut field.flow_directions,
+                std::iter::once((key.tile(), 0)),
+            );
+            self.fields.insert(key, field);
+        }
+
+        let field = self.fields.get_mut(&key).expect("field was just inserted");
+        field.last_used = clock;
+        field
+    }
+
+    fn evict_lru(&mut self) {
+        if let Some(key) = self
+            .fields
+            .iter()
+            .min_by_key(|(_, f)| f.last_used)
+            .map(|(k, _)| *k)
+        {
+            self.fields.remove(&key);
+        }
+    }
+}
+
+/// The cache together with the traversal costs its fields are built from
+#[derive(SystemParam)]
+pub struct GoalFields<'w> {
+    cache: ResMut<'w, FlowFieldCache>,
+    traversal: Res<'w, TraversalField>,
+}
+
+impl GoalFields<'_> {
+    /// Get the field for a goal, building or repairing it if needed
+    pub fn get(&mut self, key: GoalKey) -> &GoalField {
+        self.cache.get_or_build(key, &self.traversal)
+    }
+
+    /// The shared field toward every goal at once
+    pub fn traversal(&self) -> &TraversalField {
+        &self.traversal
+    }
+}
+
+/// Traversal costs of the tiles in a few chunks
+fn chunk_costs(traversal: &TraversalField, chunks: &[IVec3]) -> hashbrown::HashMap<IVec3, u32> {
+    chunks
+        .iter()
+        .flat_map(|chunk| Region::chunk(*chunk).positions())
+        .filter_map(|pos| traversal.costs.get(&pos).map(|cost| (pos, *cost)))
+        .collect()
+}
+
+/// System to drop fields whose breach no longer exists
+pub fn prune_flow_field_cache(mut cache: ResMut<FlowFieldCache>, breach_points: Res<BreachPoints>) {
+    if !breach_points.is_changed() {
+        return;
+    }
+    cache.fields.retain(|key, _| match key {
+        GoalKey::Breach(pos) => breach_points.at_position(*pos).is_some(),
+        _ => true,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::{update_traversal_field, TargetField};
+    use crate::world::{GameWorld, Tile, TileKind};
+
+    /// One open chunk with its traversal field built
+    fn traversal_app() -> App {
+        let mut world = GameWorld::new();
+        world.chunks.insert(IVec3::ZERO, Default::default());
+        let mut app = App::new();
+        app.insert_resource(world)
+            .init_resource::<TraversalField>()
+            .init_resource::<BreachPoints>()
+            .init_resource::<TargetField>()
+            .add_systems(Update, update_traversal_field);
+        app.update();
+        app
+    }
+
+    #[test]
+    fn least_recently_used_field_is_evicted() {
+        let app = traversal_app();
+        let traversal = app.world().resource::<TraversalField>();
+        let [a, b, c] = [1, 2, 3].map(|x| GoalKey::Position(IVec3::new(x, 0, 0)));
+        let mut cache = FlowFieldCache::new(2);
+
+        cache.get_or_build(a, traversal);
+        cache.get_or_build(b, traversal);
+        cache.get_or_build(a, traversal);
+        cache.get_or_build(c, traversal);
+
+        assert!(cache.fields.contains_key(&a));
+        assert!(!cache.fields.contains_key(&b));
+        assert!(cache.fields.contains_key(&c));
+    }
+
+    #[test]
+    fn repaired_field_matches_a_fresh_one() {
+        let mut app = traversal_app();
+        let goal = GoalKey::Breach(IVec3::new(2, 8, 0));
+        let mut cache = FlowFieldCache::default();
+        cache.get_or_build(goal, app.world().resource::<TraversalField>());
+
+        // Wall off most of the chunk's bottom layer
+        let mut world = app.world_mut().resource_mut::<GameWorld>();
+        for y in 0..14 {
+            world.set_tile(IVec3::new(6, y, 0), Tile::from_kind(TileKind::STONE));
+        }
+        app.update();
+
+        let traversal = app.world().resource::<TraversalField>();
+        let repaired = cache.get_or_build(goal, traversal);
+        assert_eq!(repaired.generation, traversal.generation);
+        let mut fresh = FlowFieldCache::default();
+        let expected = fresh.get_or_build(goal, traversal);
+        assert_eq!(repaired.distances, expected.distances);
+        assert!(repaired.distance(IVec3::new(10, 8, 0)) > 8);
+    }
+}
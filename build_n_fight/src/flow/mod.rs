@@ -5,17 +5,26 @@
 //! - Target value field: What's worth attacking? (Higher = more attractive)
 //!
 //! Ants pathfind using traversal costs, but choose destinations using target values.
+//! Long routes are planned over a coarse chunk portal graph first.
+//! Goal-specific fields (one breach, one nest) live in an LRU cache.
 
 use bevy::prelude::*;
 
 mod breach;
+mod cache;
+mod portal;
 mod target;
 mod traversal;
 
 pub use breach::*;
+pub use cache::*;
+pub use portal::*;
 pub use target::*;
 pub use traversal::*;
 
+// The movement neighbourhood is tile geometry, owned by the world
+pub use crate::world::NEIGHBORS;
+
 pub struct FlowPlugin;
 
 impl Plugin for FlowPlugin {
@@ -23,11 +32,18 @@ impl Plugin for FlowPlugin {
         app.init_resource::<TraversalField>()
             .init_resource::<TargetField>()
             .init_resource::<BreachPoints>()
+            .init_resource::<PortalGraph>()
+            .init_resource::<FlowFieldCache>()
             .add_event::<BreachCreatedEvent>()
+            .add_event::<BreachClosedEvent>()
             .add_systems(Update, (
                 update_traversal_field,
+                update_portal_graph,
                 update_target_field,
-                manage_breach_points,
+                (detect_breaches, manage_breach_points)
+                    .chain()
+                    .before(crate::world::update_enclosure),
+                prune_flow_field_cache,
             ));
     }
 }
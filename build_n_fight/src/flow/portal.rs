@@ -0,0 +1,388 @@
+//! Hierarchical pathfinding - chunk portal graph
+//!
+//! Coarse layer over the 16x16x16 chunks. Each shared chunk face gets one
+//! portal at its cheapest crossing, and portals inside a chunk are linked by
+//! their local traversal cost. Leaders plan long routes over portals and only
+//! consult the fine `TraversalField` inside the current and next chunk.
+
+use super::NEIGHBORS;
+use crate::world::{chunk_position, GameWorld, TileChanges, CHUNK_SIZE};
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A crossing from one chunk into its neighbour
+#[derive(Debug, Clone, Copy)]
+pub struct Portal {
+    /// Tile on this side of the face
+    pub from: IVec3,
+    /// Tile on the neighbour's side of the face
+    pub to: IVec3,
+    /// Cost of stepping across (cost of entering `to`)
+    pub cost: u32,
+}
+
+/// Portal data for a single chunk
+#[derive(Default)]
+pub struct ChunkPortals {
+    /// Cheapest crossing on each face that has a loaded neighbour
+    pub exits: Vec<Portal>,
+    /// Local cost between portal tiles of this chunk (tile -> [(tile, cost)])
+    pub interior: hashbrown::HashMap<IVec3, Vec<(IVec3, u32)>>,
+}
+
+/// Coarse chunk-level navigation graph
+#[derive(Resource, Default)]
+pub struct PortalGraph {
+    pub chunks: hashbrown::HashMap<IVec3, ChunkPortals>,
+    /// Chunks whose portals need rebuilding
+    pub dirty: hashbrown::HashSet<IVec3>,
+}
+
+impl PortalGraph {
+    /// Mark a chunk (and the faces it shares) for rebuild
+    pub fn mark_dirty(&mut self, chunk: IVec3) {
+        self.dirty.insert(chunk);
+    }
+
+    /// Rebuild portals for all dirty chunks
+    pub fn rebuild(&mut self, world: &GameWorld) {
+        if self.dirty.is_empty() {
+            return;
+        }
+
+        // A face change affects both sides, and interiors of every chunk
+        // touching a rebuilt face.
+        let mut touched: hashbrown::HashSet<IVec3> = hashbrown::HashSet::new();
+        for chunk in self.dirty.drain() {
+            touched.insert(chunk);
+            for offset in NEIGHBORS {
+                touched.insert(chunk + offset);
+            }
+        }
+
+        for chunk in &touched {
+            if world.chunks.contains_key(chunk) {
+                let exits = find_exits(world, *chunk);
+                self.chunks.entry(*chunk).or_default().exits = exits;
+            } else {
+                self.chunks.remove(chunk);
+            }
+        }
+
+        for chunk in &touched {
+            if !self.chunks.contains_key(chunk) {
+                continue;
+            }
+            let nodes = self.nodes_in(*chunk);
+            let mut interior = hashbrown::HashMap::new();
+            for node in &nodes {
+                let reached = local_dijkstra(world, *chunk, *node);
+                let edges = nodes
+                    .iter()
+                    .filter(|other| *other != node)
+                    .filter_map(|other| reached.get(other).map(|cost| (*other, *cost)))
+                    .collect();
+                interior.insert(*node, edges);
+            }
+            if let Some(portals) = self.chunks.get_mut(chunk) {
+                portals.interior = interior;
+            }
+        }
+    }
+
+    /// All portal tiles lying inside a chunk (own exits and neighbours' entries)
+    fn nodes_in(&self, chunk: IVec3) -> Vec<IVec3> {
+        let mut nodes: Vec<IVec3> = Vec::new();
+        if let Some(portals) = self.chunks.get(&chunk) {
+            nodes.extend(portals.exits.iter().map(|p| p.from));
+        }
+        for offset in NEIGHBORS {
+            if let Some(neighbor) = self.chunks.get(&(chunk + offset)) {
+                nodes.extend(
+                    neighbor
+                        .exits
+                        .iter()
+                        .filter(|p| chunk_position(p.to) == chunk)
+                        .map(|p| p.to),
+                );
+            }
+        }
+        nodes.sort_by_key(|p| p.to_array());
+        nodes.dedup();
+        nodes
+    }
+
+    /// Plan a coarse route between two tiles.
+    ///
+    /// Returns waypoints (portal tiles, ending with `to`), or None if the
+    /// chunks aren't connected. Within a single chunk the route is just `to`.
+    pub fn plan_route(&self, world: &GameWorld, from: IVec3, to: IVec3) -> Option<Vec<IVec3>> {
+        let start_chunk = chunk_position(from);
+        let goal_chunk = chunk_position(to);
+        if start_chunk == goal_chunk {
+            return Some(vec![to]);
+        }
+        if !self.chunks.contains_key(&start_chunk) || !self.chunks.contains_key(&goal_chunk) {
+            return None;
+        }
+
+        // Local legs into and out of the portal graph
+        let start_nodes = self.nodes_in(start_chunk);
+        let from_start = local_dijkstra(world, start_chunk, from);
+        let goal_nodes = self.nodes_in(goal_chunk);
+        let to_goal = local_dijkstra(world, goal_chunk, to);
+
+        // A* over portal tiles; Manhattan distance is admissible (min cost 1)
+        let heuristic = |pos: IVec3| {
+            let d = (to - pos).abs();
+            (d.x + d.y + d.z) as u32
+        };
+        let mut best: hashbrown::HashMap<IVec3, u32> = hashbrown::HashMap::new();
+        let mut came_from: hashbrown::HashMap<IVec3, IVec3> = hashbrown::HashMap::new();
+        let mut open = BinaryHeap::new();
+
+        for node in start_nodes {
+            if let Some(cost) = from_start.get(&node) {
+                best.insert(node, *cost);
+                open.push(Reverse((cost + heuristic(node), node.to_array())));
+            }
+        }
+
+        let mut finish: Option<(IVec3, u32)> = None;
+        while let Some(Reverse((_, node))) = open.pop() {
+            let node = IVec3::from_array(node);
+            let cost = best[&node];
+
+            if let Some((_, total)) = finish {
+                if cost + heuristic(node) >= total {
+                    break;
+                }
+            }
+
+            if chunk_position(node) == goal_chunk && goal_nodes.contains(&node) {
+                if let Some(leg) = to_goal.get(&node) {
+                    let total = cost.saturating_add(*leg);
+                    if finish.is_none_or(|(_, t)| total < t) {
+                        finish = Some((node, total));
+                    }
+                }
+            }
+
+            for (next, step) in self.edges_from(node) {
+                let next_cost = cost.saturating_add(step);
+                if next_cost < *best.get(&next).unwrap_or(&u32::MAX) {
+                    best.insert(next, next_cost);
+                    came_from.insert(next, node);
+                    open.push(Reverse((next_cost + heuristic(next), next.to_array())));
+                }
+            }
+        }
+
+        let (mut node, _) = finish?;
+        let mut route = vec![to, node];
+        while let Some(prev) = came_from.get(&node) {
+            route.push(*prev);
+            node = *prev;
+        }
+        route.reverse();
+        Some(route)
+    }
+
+    /// Outgoing edges of a portal tile: interior links and face crossings
+    fn edges_from(&self, node: IVec3) -> Vec<(IVec3, u32)> {
+        let Some(portals) = self.chunks.get(&chunk_position(node)) else {
+            return Vec::new();
+        };
+        let mut edges = portals.interior.get(&node).cloned().unwrap_or_default();
+        edges.extend(
+            portals
+                .exits
+                .iter()
+                .filter(|p| p.from == node)
+                .map(|p| (p.to, p.cost)),
+        );
+        edges
+    }
+}
+
+/// Find the cheapest crossing on each face of a chunk
+fn find_exits(world: &GameWorld, chunk: IVec3) -> Vec<Portal> {
+    let size = CHUNK_SIZE as i32;
+    let origin = chunk * size;
+    let mut exits = Vec::new();
+
+    for offset in NEIGHBORS {
+        if !world.chunks.contains_key(&(chunk + offset)) {
+            continue;
+        }
+
+        let mut best: Option<(u32, Portal)> = None;
+        for a in 0..size {
+            for b in 0..size {
+                // Walk the face perpendicular to `offset`
+                let local = if offset.x != 0 {
+                    IVec3::new(if offset.x > 0 { size - 1 } else { 0 }, a, b)
+                } else if offset.y != 0 {
+                    IVec3::new(a, if offset.y > 0 { size - 1 } else { 0 }, b)
+                } else {
+                    IVec3::new(a, b, if offset.z > 0 { size - 1 } else { 0 })
+                };
+                let from = origin + local;
+                let to = from + offset;
+                let (Some(from_tile), Some(to_tile)) = (world.get_tile(from), world.get_tile(to))
+                else {
+                    continue;
+                };
+                let cost = to_tile.traversal_cost();
+                let score = from_tile.traversal_cost().saturating_add(cost);
+                if best.is_none_or(|(s, _)| score < s) {
+                    best = Some((score, Portal { from, to, cost }));
+                }
+            }
+        }
+
+        if let Some((_, portal)) = best {
+            exits.push(portal);
+        }
+    }
+
+    exits
+}
+
+/// Dijkstra confined to one chunk; entering a tile costs its traversal cost
+fn local_dijkstra(world: &GameWorld, chunk: IVec3, start: IVec3) -> hashbrown::HashMap<IVec3, u32> {
+    let mut dist = hashbrown::HashMap::new();
+    let mut open = BinaryHeap::new();
+    dist.insert(start, 0u32);
+    open.push(Reverse((0u32, start.to_array())));
+
+    while let Some(Reverse((cost, pos))) = open.pop() {
+        let pos = IVec3::from_array(pos);
+        if cost > dist[&pos] {
+            continue;
+        }
+        for offset in NEIGHBORS {
+            let next = pos + offset;
+            if chunk_position(next) != chunk {
+                continue;
+            }
+            let Some(tile) = world.get_tile(next) else {
+                continue;
+            };
+            let next_cost = cost.saturating_add(tile.traversal_cost());
+            if next_cost < *dist.get(&next).unwrap_or(&u32::MAX) {
+                dist.insert(next, next_cost);
+                open.push(Reverse((next_cost, next.to_array())));
+            }
+        }
+    }
+
+    dist
+}
+
+/// System to keep the portal graph in sync with the world
+pub fn update_portal_graph(
+    mut graph: ResMut<PortalGraph>,
+    world: Res<GameWorld>,
+    mut tile_events: TileChanges,
+) {
+    for chunk in tile_events.chunks() {
+        graph.mark_dirty(chunk);
+    }
+
+    // Newly loaded or unloaded chunks
+    let added: Vec<IVec3> = world
+        .chunks
+        .keys()
+        .filter(|pos| !graph.chunks.contains_key(*pos))
+        .copied()
+        .collect();
+    let removed: Vec<IVec3> = graph
+        .chunks
+        .keys()
+        .filter(|pos| !world.chunks.contains_key(*pos))
+        .copied()
+        .collect();
+    for chunk in added.into_iter().chain(removed) {
+        graph.mark_dirty(chunk);
+    }
+
+    graph.rebuild(&world);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{Region, Tile, TileKind};
+
+    /// Three open chunks in a row, split by stone walls with one gap each
+    fn walled_world(gaps: [IVec3; 2]) -> GameWorld {
+        let size = CHUNK_SIZE as i32;
+        let mut world = GameWorld::new();
+        for x in 0..3 {
+            world.chunks.insert(IVec3::new(x, 0, 0), Default::default());
+        }
+        for gap in gaps {
+            let corner = IVec3::new(gap.x, size - 1, size - 1);
+            let wall = Region::cuboid(gap.with_y(0).with_z(0), corner);
+            world.fill_region(wall, Tile::from_kind(TileKind::STONE));
+            world.set_tile(gap, Tile::Air);
+        }
+        world
+    }
+
+    /// Cheapest fine path cost between two tiles over the whole world
+    fn fine_cost(world: &GameWorld, from: IVec3, to: IVec3) -> u32 {
+        let mut dist = hashbrown::HashMap::new();
+        let mut open = BinaryHeap::new();
+        dist.insert(from, 0u32);
+        open.push(Reverse((0u32, from.to_array())));
+        while let Some(Reverse((cost, pos))) = open.pop() {
+            let pos = IVec3::from_array(pos);
+            if pos == to {
+                return cost;
+            }
+            if cost > dist[&pos] {
+                continue;
+            }
+            for offset in NEIGHBORS {
+                let next = pos + offset;
+                let Some(tile) = world.get_tile(next) else {
+                    continue;
+                };
+                let next_cost = cost + tile.traversal_cost();
+                if next_cost < *dist.get(&next).unwrap_or(&u32::MAX) {
+                    dist.insert(next, next_cost);
+                    open.push(Reverse((next_cost, next.to_array())));
+                }
+            }
+        }
+        u32::MAX
+    }
+
+    #[test]
+    fn portal_route_matches_the_fine_path() {
+        let gaps = [IVec3::new(15, 3, 0), IVec3::new(31, 12, 4)];
+        let world = walled_world(gaps);
+        let mut graph = PortalGraph::default();
+        for chunk in world.chunks.keys() {
+            graph.mark_dirty(*chunk);
+        }
+        graph.rebuild(&world);
+
+        let (from, to) = (IVec3::new(2, 10, 0), IVec3::new(40, 5, 2));
+        let route = graph.plan_route(&world, from, to).expect("chunks are connected");
+        let crossings = [gaps[0], gaps[0] + IVec3::X, gaps[1], gaps[1] + IVec3::X];
+        assert_eq!(route, [crossings.as_slice(), &[to]].concat());
+
+        // Following the waypoints costs exactly as much as the best fine path
+        let mut legs = 0;
+        let mut at = from;
+        for waypoint in route {
+            legs += fine_cost(&world, at, waypoint);
+            at = waypoint;
+        }
+        assert_eq!(legs, fine_cost(&world, from, to));
+    }
+}
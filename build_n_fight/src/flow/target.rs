@@ -3,6 +3,10 @@
 //! Higher value = more attractive target
 //! Used to decide WHERE to go, not HOW to get there.
 
+use super::BreachPoints;
+use crate::combat::MountPoint;
+use crate::player::{PlayerStructure, StructureKind};
+use crate::world::{Tile, TileChanges};
 use bevy::prelude::*;
 
 /// Target value field resource
@@ -10,6 +14,8 @@ use bevy::prelude::*;
 pub struct TargetField {
     /// Value of each target position
     pub values: hashbrown::HashMap<IVec3, u32>,
+    /// Damaged walls and their value, maintained from tile changes
+    pub damaged_walls: hashbrown::HashMap<IVec3, u32>,
 }
 
 /// Target value constants
@@ -51,14 +57,73 @@ impl TargetField {
     }
 }
 
+/// Value of a player structure kind
+pub fn structure_value(kind: StructureKind) -> u32 {
+    match kind {
+        StructureKind::Keep => target_values::KEEP,
+        StructureKind::Barracks => target_values::BARRACKS,
+        StructureKind::Armory => target_values::ARMORY,
+        StructureKind::Gate => target_values::GATE,
+    }
+}
+
+/// Value of a wall tile - scales with how damaged it is (0 when intact)
+pub fn damaged_wall_value(tile: &Tile) -> u32 {
+    match tile {
+        Tile::Wall { hp, max_hp, .. } if hp < max_hp && *max_hp > 0 => {
+            let missing = (*max_hp - *hp) as u32;
+            (target_values::DAMAGED_WALL * missing / *max_hp as u32).max(1)
+        }
+        _ => 0,
+    }
+}
+
 /// System to update target field based on structures and units
+///
+/// Values come from:
+/// - Player structures (Keep, Barracks, etc.)
+/// - Damaged walls (higher value for more damaged)
+/// - Wall defenders (occupied mount points)
+/// - Breach points (see breach.rs)
+///
+/// Overlapping sources keep the highest value.
 pub fn update_target_field(
-    _field: ResMut<TargetField>,
-    // TODO: Query player structures and units to populate values
+    mut field: ResMut<TargetField>,
+    mut tile_events: TileChanges,
+    structures: Query<(&PlayerStructure, &Transform)>,
+    mounts: Query<(&MountPoint, &Transform)>,
+    breach_points: Res<BreachPoints>,
 ) {
-    // This will be populated by:
-    // - Player structures (Keep, Barracks, etc.)
-    // - Damaged walls (higher value for more damaged)
-    // - Wall defenders (units on walls)
-    // - Breach points (see breach.rs)
+    for event in tile_events.read() {
+        let value = damaged_wall_value(&event.new_tile);
+        if value > 0 {
+            field.damaged_walls.insert(event.position, value);
+        } else if field.damaged_walls.contains_key(&event.position) {
+            field.damaged_walls.remove(&event.position);
+        }
+    }
+
+    let mut values = field.damaged_walls.clone();
+    let mut raise = |pos: IVec3, value: u32| {
+        let entry = values.entry(pos).or_insert(0);
+        *entry = (*entry).max(value);
+    };
+
+    for (structure, transform) in structures.iter() {
+        raise(transform.translation.as_ivec3(), structure_value(structure.kind));
+    }
+    for (mount, transform) in mounts.iter() {
+        if mount.is_occupied() {
+            raise(transform.translation.as_ivec3(), target_values::WALL_DEFENDER);
+        }
+    }
+    for breach in &breach_points.points {
+        raise(breach.position, target_values::BREACH_POINT);
+    }
+
+    // Only touch the resource when something changed, so the flow field
+    // doesn't treat every frame as a goal change.
+    if field.values != values {
+        field.values = values;
+    }
 }
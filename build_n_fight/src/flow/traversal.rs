@@ -3,18 +3,50 @@
 //! Key insight: Traversal cost = tile HP
 //! Damaged walls have lower cost, naturally attracting more ants.
 
-use crate::world::{GameWorld, TileChangedEvent};
+use super::{BreachPoints, TargetField, target_values, NEIGHBORS};
+use crate::world::{GameWorld, Region, Tile, CHUNK_SIZE};
 use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Target values at or above this become flow field goals
+pub const GOAL_VALUE_THRESHOLD: u32 = target_values::BARRACKS;
+
+/// Generations of chunk changes remembered for repairing derived fields
+const CHANGE_LOG_LEN: usize = 32;
+
+/// Hazards that add traversal cost on top of a tile's own cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CostPenalty {
+    Water,
+    Fire,
+}
 
 /// Traversal cost field resource
 #[derive(Resource, Default)]
 pub struct TraversalField {
     /// Cost to traverse each tile (position -> cost)
     pub costs: hashbrown::HashMap<IVec3, u32>,
+    /// Accumulated cost from each tile to the nearest goal
+    pub distances: hashbrown::HashMap<IVec3, u32>,
     /// Direction to flow toward goal from each tile
     pub flow_directions: hashbrown::HashMap<IVec3, IVec3>,
-    /// Whether the field needs recalculation
+    /// Goals that come from resources rather than tiles (breaches, targets)
+    pub extra_goals: hashbrown::HashSet<IVec3>,
+    /// Chunks currently represented in the field
+    pub chunk_index: hashbrown::HashSet<IVec3>,
+    /// Whether the whole field needs recalculation
     pub dirty: bool,
+    /// Bumped every time costs change (lets derived fields detect staleness)
+    pub generation: u64,
+    /// Hazard costs per tile, by source
+    penalties: hashbrown::HashMap<(CostPenalty, IVec3), u32>,
+    /// Summed hazard cost per tile
+    penalty_totals: hashbrown::HashMap<IVec3, u32>,
+    /// Chunks whose hazard costs changed since the last repair
+    penalty_chunks: hashbrown::HashSet<IVec3>,
+    /// Chunks re-costed by each recent generation, oldest first
+    change_log: VecDeque<(u64, Vec<IVec3>)>,
 }
 
 impl TraversalField {
@@ -23,56 +55,381 @@ impl TraversalField {
         *self.costs.get(&pos).unwrap_or(&u32::MAX)
     }
 
+    /// Get accumulated cost to the nearest goal (u32::MAX if unreachable)
+    pub fn distance(&self, pos: IVec3) -> u32 {
+        *self.distances.get(&pos).unwrap_or(&u32::MAX)
+    }
+
     /// Get the flow direction from a position (normalized direction toward goal)
     pub fn flow_direction(&self, pos: IVec3) -> Option<IVec3> {
         self.flow_directions.get(&pos).copied()
     }
 
-    /// Mark field as needing recalculation
+    /// Mark the whole field as needing recalculation
     pub fn mark_dirty(&mut self) {
         self.dirty = true;
     }
+
+    /// Set the extra cost one hazard adds to a tile (0 clears it)
+    pub fn set_penalty(&mut self, source: CostPenalty, pos: IVec3, amount: u32) {
+        let previous = if amount == 0 {
+            self.penalties.remove(&(source, pos))
+        } else {
+            self.penalties.insert((source, pos), amount)
+        };
+        if previous.unwrap_or(0) == amount {
+            return;
+        }
+
+        let total = self.penalty(pos) - previous.unwrap_or(0) + amount;
+        if total == 0 {
+            self.penalty_totals.remove(&pos);
+        } else {
+            self.penalty_totals.insert(pos, total);
+        }
+        self.penalty_chunks
+            .insert(pos.div_euclid(IVec3::splat(CHUNK_SIZE as i32)));
+    }
+
+    /// Drop every hazard cost (the hazards re-report theirs)
+    pub fn clear_penalties(&mut self) {
+        self.penalties.clear();
+        self.penalty_totals.clear();
+        self.dirty = true;
+    }
+
+    /// Total hazard cost on a tile
+    pub fn penalty(&self, pos: IVec3) -> u32 {
+        *self.penalty_totals.get(&pos).unwrap_or(&0)
+    }
+
+    /// Chunks whose costs changed after `generation`, or None if that is
+    /// too long ago to tell (derived fields then rebuild from scratch)
+    pub fn chunks_changed_since(&self, generation: u64) -> Option<hashbrown::HashSet<IVec3>> {
+        if generation == self.generation {
+            return Some(hashbrown::HashSet::new());
+        }
+        let (oldest, _) = self.change_log.front()?;
+        if *oldest > generation + 1 {
+            return None;
+        }
+        Some(
+            self.change_log
+                .iter()
+                .filter(|(changed, _)| *changed > generation)
+                .flat_map(|(_, chunks)| chunks.iter().copied())
+                .collect(),
+        )
+    }
 }
 
-/// System to update traversal field when tiles change
+/// System to repair the traversal field for chunks whose tiles changed
+///
+/// Only chunks with `Chunk::flow_field_dirty` set (plus every tile whose
+/// flow path ran through them) are re-propagated; the rest of the field is
+/// left untouched. Marking the whole field dirty forces a full rebuild.
 pub fn update_traversal_field(
     mut field: ResMut<TraversalField>,
-    world: Res<GameWorld>,
-    mut tile_events: EventReader<TileChangedEvent>,
+    mut world: ResMut<GameWorld>,
+    breach_points: Res<BreachPoints>,
+    target_field: Res<TargetField>,
 ) {
-    // Mark dirty if any tiles changed
-    for event in tile_events.read() {
-        // Only recalculate if the cost changed
-        let old_cost = event.old_tile.traversal_cost();
-        let new_cost = event.new_tile.traversal_cost();
-        if old_cost != new_cost {
-            field.dirty = true;
-            // Update the single tile cost immediately
-            field.costs.insert(event.position, new_cost);
-        }
+    let mut dirty_chunks: Vec<IVec3> = world
+        .chunks
+        .iter()
+        .filter(|(pos, chunk)| {
+            field.dirty || chunk.flow_field_dirty || field.penalty_chunks.contains(*pos)
+        })
+        .map(|(pos, _)| *pos)
+        .collect();
+    field.penalty_chunks.clear();
+
+    // Goal positions that don't come from tiles (breaches, valuable targets)
+    let mut removed_goals = Vec::new();
+    let mut added_goals = Vec::new();
+    if field.dirty || breach_points.is_changed() || target_field.is_changed() {
+        let goals: hashbrown::HashSet<IVec3> =
+            resource_goals(&breach_points, &target_field).collect();
+        removed_goals.extend(field.extra_goals.difference(&goals).copied());
+        added_goals.extend(goals.difference(&field.extra_goals).copied());
+        field.extra_goals = goals;
+    }
+
+    // Chunks that vanished since the last repair
+    let stale_chunks: Vec<IVec3> = field
+        .chunk_index
+        .iter()
+        .filter(|pos| !world.chunks.contains_key(*pos))
+        .copied()
+        .collect();
+    dirty_chunks.extend(stale_chunks);
+
+    if dirty_chunks.is_empty() && removed_goals.is_empty() && added_goals.is_empty() {
+        return;
     }
 
-    // Full recalculation if dirty
-    // TODO: Implement proper flow field algorithm (Dijkstra from goals)
+    repair_flow_field(&mut field, &world, &dirty_chunks, &removed_goals, &added_goals);
+    field.generation += 1;
     if field.dirty {
-        recalculate_flow_field(&mut field, &world);
-        field.dirty = false;
+        // Everything changed - derived fields have to start over
+        field.change_log.clear();
+    } else {
+        let generation = field.generation;
+        field.change_log.push_back((generation, dirty_chunks.clone()));
+        if field.change_log.len() > CHANGE_LOG_LEN {
+            field.change_log.pop_front();
+        }
     }
+    field.dirty = false;
+
+    for chunk_pos in &dirty_chunks {
+        if let Some(chunk) = world.chunks.get_mut(chunk_pos) {
+            chunk.flow_field_dirty = false;
+        }
+    }
+}
+
+/// Goals provided by breach points and high-value targets
+fn resource_goals<'a>(
+    breach_points: &'a BreachPoints,
+    target_field: &'a TargetField,
+) -> impl Iterator<Item = IVec3> + 'a {
+    breach_points.points.iter().map(|b| b.position).chain(
+        target_field
+            .values
+            .iter()
+            .filter(|(_, value)| **value >= GOAL_VALUE_THRESHOLD)
+            .map(|(pos, _)| *pos),
+    )
+}
+
+/// Is this tile a player structure the ants path toward?
+fn is_goal_tile(tile: &Tile) -> bool {
+    matches!(tile, Tile::Wall { .. } | Tile::Floor { .. })
 }
 
-/// Recalculate the entire flow field using Dijkstra from goals
-fn recalculate_flow_field(field: &mut TraversalField, world: &GameWorld) {
-    // TODO: Implement proper flow field calculation
-    // 1. Find all goal positions (player structures, breach points)
-    // 2. Dijkstra outward from goals
-    // 3. Store direction toward lowest-cost neighbor at each tile
+/// Re-propagate the region affected by dirty chunks and goal changes.
+///
+/// Costs inside dirty chunks are refreshed, then those tiles, removed and
+/// added goals are repaired with `repair_region`.
+fn repair_flow_field(
+    field: &mut TraversalField,
+    world: &GameWorld,
+    dirty_chunks: &[IVec3],
+    removed_goals: &[IVec3],
+    added_goals: &[IVec3],
+) {
+    let mut invalid: hashbrown::HashSet<IVec3> = hashbrown::HashSet::new();
+    let mut region_goals = Vec::new();
+
+    for chunk_pos in dirty_chunks {
+        let origin = *chunk_pos * CHUNK_SIZE as i32;
+        match world.chunks.get(chunk_pos) {
+            Some(chunk) => {
+                field.chunk_index.insert(*chunk_pos);
+                for (local_pos, tile) in chunk.iter_tiles() {
+                    let pos = origin + local_pos.as_ivec3();
+                    let cost = tile.traversal_cost().saturating_add(field.penalty(pos));
+                    field.costs.insert(pos, cost);
+                    if is_goal_tile(tile) {
+                        region_goals.push(pos);
+                    }
+                    invalid.insert(pos);
+                }
+            }
+            None => {
+                // Chunk unloaded - forget it entirely
+                field.chunk_index.remove(chunk_pos);
+                for pos in Region::chunk(*chunk_pos).positions() {
+                    field.costs.remove(&pos);
+                    invalid.insert(pos);
+                }
+            }
+        }
+    }
+    invalid.extend(removed_goals.iter().copied());
+    invalid.extend(added_goals.iter().copied());
 
-    // For now, just update costs from world
-    field.costs.clear();
-    for (chunk_pos, chunk) in world.chunks.iter() {
-        for (local_pos, tile) in chunk.iter_tiles() {
-            let world_pos = *chunk_pos * 16 + local_pos.as_ivec3();
-            field.costs.insert(world_pos, tile.traversal_cost());
+    let TraversalField {
+        costs,
+        distances,
+        flow_directions,
+        extra_goals,
+        ..
+    } = field;
+    let goals = region_goals.into_iter().chain(extra_goals.iter().copied());
+    repair_region(costs, distances, flow_directions, invalid, goals);
+}
+
+/// Repair a flow field after the costs of some tiles changed.
+///
+/// 1. Invalidate those tiles and everything downstream (tiles whose flow
+///    direction leads into an invalidated tile).
+/// 2. Re-seed Dijkstra from the goals inside the region plus the
+///    still-valid tiles bordering it, and let it relax outward.
+pub fn repair_region(
+    costs: &hashbrown::HashMap<IVec3, u32>,
+    distances: &mut hashbrown::HashMap<IVec3, u32>,
+    flow_directions: &mut hashbrown::HashMap<IVec3, IVec3>,
+    mut invalid: hashbrown::HashSet<IVec3>,
+    goals: impl Iterator<Item = IVec3>,
+) {
+    // Spread invalidation downstream
+    let mut frontier: Vec<IVec3> = invalid.iter().copied().collect();
+    while let Some(pos) = frontier.pop() {
+        for offset in NEIGHBORS {
+            let neighbor = pos + offset;
+            if flow_directions.get(&neighbor) == Some(&-offset) && invalid.insert(neighbor) {
+                frontier.push(neighbor);
+            }
         }
     }
+
+    for pos in &invalid {
+        distances.remove(pos);
+        flow_directions.remove(pos);
+    }
+
+    // Seeds: goals in the region, and the valid border
+    let mut seeds: Vec<(IVec3, u32)> = goals
+        .filter(|g| invalid.contains(g))
+        .map(|g| (g, 0))
+        .collect();
+    for pos in &invalid {
+        for offset in NEIGHBORS {
+            let neighbor = *pos + offset;
+            if invalid.contains(&neighbor) {
+                continue;
+            }
+            if let Some(dist) = distances.get(&neighbor) {
+                seeds.push((neighbor, *dist));
+            }
+        }
+    }
+
+    dijkstra(costs, distances, flow_directions, seeds.into_iter());
+}
+
+/// Multi-source Dijkstra over the 6-neighbourhood.
+///
+/// Stepping from a tile onto its neighbour costs the neighbour's traversal
+/// cost, so a goal wall still "costs" its HP to break - damaged walls win.
+/// Each relaxed tile stores the step back toward the tile it was reached from.
+pub fn dijkstra(
+    costs: &hashbrown::HashMap<IVec3, u32>,
+    distances: &mut hashbrown::HashMap<IVec3, u32>,
+    flow_directions: &mut hashbrown::HashMap<IVec3, IVec3>,
+    seeds: impl Iterator<Item = (IVec3, u32)>,
+) {
+    let distance = |distances: &hashbrown::HashMap<IVec3, u32>, pos: IVec3| {
+        *distances.get(&pos).unwrap_or(&u32::MAX)
+    };
+    let mut open = BinaryHeap::new();
+
+    for (pos, dist) in seeds {
+        if !costs.contains_key(&pos) {
+            continue;
+        }
+        if dist < distance(distances, pos) {
+            distances.insert(pos, dist);
+            flow_directions.remove(&pos);
+        }
+        open.push(Reverse((dist, pos.to_array())));
+    }
+
+    while let Some(Reverse((dist, pos))) = open.pop() {
+        let pos = IVec3::from_array(pos);
+        if dist > distance(distances, pos) {
+            continue; // Stale heap entry
+        }
+
+        let step_cost = costs[&pos];
+        let next_dist = dist.saturating_add(step_cost);
+
+        for offset in NEIGHBORS {
+            let neighbor = pos + offset;
+            if !costs.contains_key(&neighbor) {
+                continue; // Ungenerated
+            }
+            if next_dist < distance(distances, neighbor) {
+                distances.insert(neighbor, next_dist);
+                flow_directions.insert(neighbor, -offset);
+                open.push(Reverse((next_dist, neighbor.to_array())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Costs = hashbrown::HashMap<IVec3, u32>;
+    type Distances = hashbrown::HashMap<IVec3, u32>;
+    type Directions = hashbrown::HashMap<IVec3, IVec3>;
+
+    /// A 12x12x2 block with costs from 1 to 9 in a fixed pattern
+    fn grid() -> Costs {
+        Region::cuboid(IVec3::ZERO, IVec3::new(11, 11, 1))
+            .positions()
+            .map(|pos| {
+                let hash = (pos.x * 7 + pos.y * 13 + pos.z * 29) * 31 % 9;
+                (pos, hash as u32 + 1)
+            })
+            .collect()
+    }
+
+    fn full_field(costs: &Costs, goals: &[IVec3]) -> (Distances, Directions) {
+        let mut distances = Distances::new();
+        let mut directions = Directions::new();
+        dijkstra(costs, &mut distances, &mut directions, goals.iter().map(|g| (*g, 0)));
+        (distances, directions)
+    }
+
+    /// Every flow direction steps to a tile exactly one step cost nearer
+    fn assert_consistent(costs: &Costs, distances: &Distances, directions: &Directions) {
+        for (pos, dir) in directions {
+            let next = *pos + *dir;
+            assert_eq!(distances[pos], distances[&next] + costs[&next], "at {pos:?}");
+        }
+    }
+
+    #[test]
+    fn dijkstra_takes_the_nearest_of_several_goals() {
+        let costs: Costs = (0..10).map(|x| (IVec3::new(x, 0, 0), 1)).collect();
+        let goals = [IVec3::new(0, 0, 0), IVec3::new(9, 0, 0)];
+        let (distances, directions) = full_field(&costs, &goals);
+
+        for x in 0..10 {
+            let pos = IVec3::new(x, 0, 0);
+            assert_eq!(distances[&pos], x.min(9 - x) as u32);
+        }
+        assert_eq!(directions.get(&IVec3::new(2, 0, 0)), Some(&IVec3::NEG_X));
+        assert_eq!(directions.get(&IVec3::new(7, 0, 0)), Some(&IVec3::X));
+        assert!(!directions.contains_key(&goals[0]) && !directions.contains_key(&goals[1]));
+        assert_consistent(&costs, &distances, &directions);
+    }
+
+    #[test]
+    fn repair_matches_a_full_rebuild() {
+        let goals = [IVec3::new(0, 0, 0), IVec3::new(11, 6, 1)];
+        let mut costs = grid();
+        let (mut distances, mut directions) = full_field(&costs, &goals);
+
+        // A wall goes up across the middle and a corridor opens beside it
+        let mut changed = hashbrown::HashSet::new();
+        for pos in Region::cuboid(IVec3::new(5, 0, 0), IVec3::new(5, 9, 1)).positions() {
+            costs.insert(pos, 500);
+            changed.insert(pos);
+        }
+        for pos in Region::cuboid(IVec3::new(7, 2, 0), IVec3::new(9, 2, 1)).positions() {
+            costs.insert(pos, 1);
+            changed.insert(pos);
+        }
+        repair_region(&costs, &mut distances, &mut directions, changed, goals.into_iter());
+
+        let (expected, _) = full_field(&costs, &goals);
+        assert_eq!(distances, expected);
+        assert_consistent(&costs, &distances, &directions);
+    }
 }
@@ -13,6 +13,7 @@ mod combat;
 mod flow;
 mod player;
 mod render;
+mod save;
 mod visibility;
 mod world;
 
@@ -57,6 +58,7 @@ fn main() {
             player::PlayerPlugin,
             visibility::VisibilityPlugin,
             render::RenderPlugin,
+            save::SavePlugin,
         ))
         // Startup systems
         .add_systems(Startup, setup)
@@ -8,14 +8,30 @@
 //!
 //! WARNING: If they die, ants get biomass!
 
+use super::{PlayerResources, PlayerStructure, StructureKind};
+use crate::flow::{FlowFieldCache, GoalKey, TraversalField};
+use crate::world::{
+    depleted_tile, ChangeCause, Deposits, EnvironmentField, GameWorld, SecondTick,
+    TileChangedEvent, TileRegistry, TileYield, WaterField, WorldGenConfig, HEAT_SUPPLY_DRAIN,
+};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
+/// Tiles per second a team member walks
+const AWAY_TEAM_SPEED: f32 = 2.0;
+/// Resource units each member at a deposit mines per second
+const MINE_RATE: u32 = 1;
+/// Resource units a team can carry home
+const CARGO_CAPACITY: u32 = 20;
+
 /// Away team component
 #[derive(Component)]
 pub struct AwayTeam {
     pub members: Vec<Entity>,
     pub mission: Mission,
     pub supplies: Supplies,
+    /// Mined resources on the way home
+    pub cargo: TileYield,
 }
 
 /// What the team is doing
@@ -43,6 +59,7 @@ impl AwayTeam {
             members,
             mission,
             supplies: Supplies::default(),
+            cargo: TileYield::default(),
         }
     }
 
@@ -56,6 +73,17 @@ impl AwayTeam {
         self.supplies.food <= 0.0
     }
 
+    /// Where the team is headed (None = home to the keep)
+    pub fn destination(&self) -> Option<IVec3> {
+        match &self.mission {
+            Mission::Scout { target_area } => Some(*target_area),
+            Mission::Mine { target_deposit } => Some(*target_deposit),
+            Mission::SealTunnel { tunnel_pos } => Some(*tunnel_pos),
+            Mission::PlantExplosive { target, .. } => Some(*target),
+            Mission::Return => None,
+        }
+    }
+
     /// Team size
     pub fn size(&self) -> usize {
         self.members.len()
@@ -65,11 +93,21 @@ impl AwayTeam {
 /// System to update away teams
 pub fn update_away_teams(
     mut teams: Query<&mut AwayTeam>,
+    members: Query<&Transform>,
+    environment: Res<EnvironmentField>,
+    config: Res<WorldGenConfig>,
+    world: Res<GameWorld>,
     time: Res<Time>,
 ) {
     for mut team in teams.iter_mut() {
-        // Consume food over time
-        team.supplies.food -= time.delta_secs();
+        // Consume food over time, faster in the heat
+        let heat = team
+            .members
+            .iter()
+            .filter_map(|member| members.get(*member).ok())
+            .map(|t| environment.conditions(&config, &world, t.translation.as_ivec3()).heat)
+            .fold(0.0, f32::max);
+        team.supplies.food -= time.delta_secs() * (1.0 + HEAT_SUPPLY_DRAIN * heat);
 
         // If out of food, force return
         if team.needs_resupply() {
@@ -81,8 +119,8 @@ pub fn update_away_teams(
             Mission::Scout { target_area } => {
                 // Move toward target, look for threats
             }
-            Mission::Mine { target_deposit } => {
-                // Move to deposit, dig, collect resources
+            Mission::Mine { .. } => {
+                // See `mine_deposits`
             }
             Mission::SealTunnel { tunnel_pos } => {
                 // Move to tunnel, use sealant
@@ -91,8 +129,150 @@ pub fn update_away_teams(
                 // Move to target, plant, run!
             }
             Mission::Return => {
-                // Head back to base
+                // See `mine_deposits` for unloading
+            }
+        }
+    }
+}
+
+/// Is a member standing at (or next to) a tile?
+fn is_at(transform: &Transform, pos: IVec3) -> bool {
+    (transform.translation.as_ivec3() - pos).abs().max_element() <= 1
+}
+
+/// System to walk away team members toward their destination
+pub fn move_away_teams(
+    teams: Query<&AwayTeam>,
+    mut members: Query<&mut Transform, (Without<AwayTeam>, Without<PlayerStructure>)>,
+    structures: Query<(&PlayerStructure, &Transform)>,
+    mut flow_cache: ResMut<FlowFieldCache>,
+    traversal: Res<TraversalField>,
+    water: Res<WaterField>,
+    time: Res<Time>,
+) {
+    let keep = structures
+        .iter()
+        .find(|(structure, _)| structure.kind == StructureKind::Keep)
+        .map(|(_, transform)| transform.translation.as_ivec3());
+
+    for team in teams.iter() {
+        let Some(goal) = team.destination().or(keep) else {
+            continue;
+        };
+        let field = flow_cache.get_or_build(GoalKey::Position(goal), &traversal);
+
+        for member in &team.members {
+            let Ok(mut transform) = members.get_mut(*member) else {
+                continue;
+            };
+            if is_at(&transform, goal) {
+                continue;
+            }
+            let pos = transform.translation.as_ivec3();
+            if let Some(dir) = field.flow_direction(pos) {
+                let step = (pos + dir).as_vec3() - transform.translation;
+                let max_step = AWAY_TEAM_SPEED * water.movement_factor(pos) * time.delta_secs();
+                transform.translation += step.clamp_length_max(max_step);
+            }
+        }
+    }
+}
+
+/// Deposits and the world they are mined out of
+#[derive(SystemParam)]
+pub struct Mining<'w> {
+    deposits: ResMut<'w, Deposits>,
+    world: ResMut<'w, GameWorld>,
+    registry: Res<'w, TileRegistry>,
+}
+
+/// System to mine deposits and bring the haul home
+///
+/// Members next to their target deposit mine it out a little each second.
+/// The team heads home once it is full or the deposit runs dry, and its
+/// cargo is unloaded into `PlayerResources` when it reaches the keep.
+pub fn mine_deposits(
+    mut teams: Query<&mut AwayTeam>,
+    members: Query<&Transform, Without<AwayTeam>>,
+    structures: Query<(&PlayerStructure, &Transform), Without<AwayTeam>>,
+    mining: Mining,
+    mut resources: ResMut<PlayerResources>,
+    mut tile_events: EventWriter<TileChangedEvent>,
+    tick: Res<SecondTick>,
+) {
+    if !tick.fired() {
+        return;
+    }
+    let Mining {
+        mut deposits,
+        mut world,
+        registry,
+    } = mining;
+
+    let keep = structures
+        .iter()
+        .find(|(structure, _)| structure.kind == StructureKind::Keep)
+        .map(|(_, transform)| transform.translation.as_ivec3());
+
+    for mut team in teams.iter_mut() {
+        match team.mission {
+            Mission::Mine { target_deposit } => {
+                let miners: Vec<Entity> = team
+                    .members
+                    .iter()
+                    .copied()
+                    .filter(|m| members.get(*m).is_ok_and(|t| is_at(t, target_deposit)))
+                    .collect();
+                if miners.is_empty() {
+                    continue;
+                }
+
+                let room = CARGO_CAPACITY.saturating_sub(team.cargo.total());
+                let units = (MINE_RATE * miners.len() as u32).min(room);
+                let mined = deposits.extract(&world, target_deposit, units);
+                team.cargo.add(mined);
+
+                let exhausted = deposits.remaining(&world, target_deposit).is_empty();
+                if exhausted {
+                    if let Some(old_tile) = world.get_tile(target_deposit).copied() {
+                        let new_tile = depleted_tile(&registry, &old_tile);
+                        if new_tile != old_tile {
+                            world.set_tile(target_deposit, new_tile);
+                            tile_events.send(TileChangedEvent {
+                                position: target_deposit,
+                                old_tile,
+                                new_tile,
+                                cause: ChangeCause::Dig,
+                                actor: Some(miners[0]),
+                            });
+                        }
+                    }
+                }
+                if exhausted || team.cargo.total() >= CARGO_CAPACITY {
+                    info!(
+                        "Away team heading home with {:?} from {:?}",
+                        team.cargo, target_deposit
+                    );
+                    team.mission = Mission::Return;
+                }
+            }
+            Mission::Return if !team.cargo.is_empty() => {
+                let Some(keep) = keep else {
+                    continue;
+                };
+                let home = team
+                    .members
+                    .iter()
+                    .any(|m| members.get(*m).is_ok_and(|t| is_at(t, keep)));
+                if home {
+                    let cargo = std::mem::take(&mut team.cargo);
+                    resources.tungsten += cargo.tungsten;
+                    resources.iron += cargo.iron;
+                    resources.wood += cargo.wood;
+                    info!("Away team unloaded {:?}", cargo);
+                }
             }
+            _ => {}
         }
     }
 }
@@ -1,6 +1,6 @@
 //! Building system - Walls, floors, structures
 
-use crate::world::{GameWorld, BuildMaterial, Tile, TileChangedEvent};
+use crate::world::{ChangeCause, GameWorld, BuildMaterial, Tile, TileChangedEvent, TileKind};
 use bevy::prelude::*;
 
 /// What we're trying to build
@@ -30,36 +30,37 @@ impl BuildableType {
 
     pub fn to_tile(&self) -> Option<Tile> {
         match self {
-            BuildableType::WoodWall => Some(Tile::Wall {
-                hp: BuildMaterial::Wood.base_hp(),
-                max_hp: BuildMaterial::Wood.base_hp(),
-                material: BuildMaterial::Wood,
-            }),
-            BuildableType::StoneWall => Some(Tile::Wall {
-                hp: BuildMaterial::Stone.base_hp(),
-                max_hp: BuildMaterial::Stone.base_hp(),
-                material: BuildMaterial::Stone,
-            }),
-            BuildableType::MetalWall => Some(Tile::Wall {
-                hp: BuildMaterial::Metal.base_hp(),
-                max_hp: BuildMaterial::Metal.base_hp(),
-                material: BuildMaterial::Metal,
-            }),
-            BuildableType::WoodFloor => Some(Tile::Floor {
-                hp: BuildMaterial::Wood.base_hp(),
-                max_hp: BuildMaterial::Wood.base_hp(),
-                material: BuildMaterial::Wood,
-            }),
-            BuildableType::StoneFloor => Some(Tile::Floor {
-                hp: BuildMaterial::Stone.base_hp(),
-                max_hp: BuildMaterial::Stone.base_hp(),
-                material: BuildMaterial::Stone,
-            }),
+            BuildableType::WoodWall => Some(Tile::from_kind(TileKind::wall(BuildMaterial::Wood))),
+            BuildableType::StoneWall => Some(Tile::from_kind(TileKind::wall(BuildMaterial::Stone))),
+            BuildableType::MetalWall => Some(Tile::from_kind(TileKind::wall(BuildMaterial::Metal))),
+            BuildableType::WoodFloor => Some(Tile::from_kind(TileKind::floor(BuildMaterial::Wood))),
+            BuildableType::StoneFloor => Some(Tile::from_kind(TileKind::floor(BuildMaterial::Stone))),
             BuildableType::Turret => None, // Turret is an entity, not a tile
         }
     }
 }
 
+/// Kinds of player structure entities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructureKind {
+    Keep,
+    Barracks,
+    Armory,
+    Gate,
+}
+
+/// A player structure entity (the keep, barracks, etc.)
+#[derive(Component)]
+pub struct PlayerStructure {
+    pub kind: StructureKind,
+}
+
+impl PlayerStructure {
+    pub fn new(kind: StructureKind) -> Self {
+        Self { kind }
+    }
+}
+
 /// Current build mode
 #[derive(Resource, Default)]
 pub struct BuildMode {
@@ -80,6 +81,7 @@ pub fn process_build_events(
     mut events: EventReader<BuildEvent>,
     mut world: ResMut<GameWorld>,
     mut resources: ResMut<super::PlayerResources>,
+    mut history: ResMut<super::UndoHistory>,
     mut tile_events: EventWriter<TileChangedEvent>,
 ) {
     for event in events.read() {
@@ -116,6 +118,14 @@ pub fn process_build_events(
                 position: event.position,
                 old_tile,
                 new_tile,
+                cause: ChangeCause::Build,
+                actor: None,
+            });
+            history.push(super::PlayerAction::Build {
+                position: event.position,
+                buildable: event.buildable,
+                replaced: old_tile,
+                placed: new_tile,
             });
 
             info!("Built {:?} at {:?}", event.buildable, event.position);
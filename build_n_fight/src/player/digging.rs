@@ -1,6 +1,6 @@
 //! Digging system - Remove tiles to create tunnels
 
-use crate::world::{GameWorld, Tile, TileChangedEvent};
+use crate::world::{ChangeCause, Deposits, GameWorld, Tile, TileChangedEvent};
 use bevy::prelude::*;
 
 /// Dig event
@@ -16,6 +16,8 @@ pub fn process_dig_events(
     mut world: ResMut<GameWorld>,
     mut tile_events: EventWriter<TileChangedEvent>,
     mut resources: ResMut<super::PlayerResources>,
+    mut history: ResMut<super::UndoHistory>,
+    mut deposits: ResMut<Deposits>,
 ) {
     for event in events.read() {
         if let Some(tile) = world.get_tile(event.position) {
@@ -26,17 +28,11 @@ pub fn process_dig_events(
 
             let old_tile = *tile;
 
-            // Give resources based on what was dug
-            match old_tile {
-                Tile::Dirt { .. } => {
-                    // Dirt gives nothing special
-                }
-                Tile::Stone { .. } => {
-                    // Stone gives iron
-                    resources.iron += 1;
-                }
-                _ => {}
-            }
+            // Give whatever was left in the tile
+            let yields = deposits.take_all(&world, event.position);
+            resources.tungsten += yields.tungsten;
+            resources.iron += yields.iron;
+            resources.wood += yields.wood;
 
             // Replace with air
             world.set_tile(event.position, Tile::Air);
@@ -45,6 +41,13 @@ pub fn process_dig_events(
                 position: event.position,
                 old_tile,
                 new_tile: Tile::Air,
+                cause: ChangeCause::Dig,
+                actor: Some(event.digger),
+            });
+            history.push(super::PlayerAction::Dig {
+                position: event.position,
+                dug: old_tile,
+                yields,
             });
 
             info!("Dug tile at {:?}", event.position);
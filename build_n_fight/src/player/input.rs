@@ -10,6 +10,7 @@ pub fn handle_input(
     mouse: Res<ButtonInput<MouseButton>>,
     mut build_mode: ResMut<BuildMode>,
     mut current_z: ResMut<CurrentZLevel>,
+    mut undo_events: EventWriter<UndoEvent>,
     // TODO: Add cursor position tracking for build/dig commands
 ) {
     // Toggle build mode with B
@@ -56,5 +57,11 @@ pub fn handle_input(
         info!("Z-level: {}", current_z.level);
     }
 
+    // Undo the last build or dig with Ctrl+Z
+    let ctrl = keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
+    if ctrl && keyboard.just_pressed(KeyCode::KeyZ) {
+        undo_events.send(UndoEvent);
+    }
+
     // TODO: Mouse click to build/dig at cursor position
 }
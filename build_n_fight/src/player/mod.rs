@@ -8,11 +8,13 @@ mod away_team;
 mod building;
 mod digging;
 mod input;
+mod undo;
 
 pub use away_team::*;
 pub use building::*;
 pub use digging::*;
 pub use input::*;
+pub use undo::*;
 
 pub struct PlayerPlugin;
 
@@ -20,13 +22,18 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerResources>()
             .init_resource::<BuildMode>()
+            .init_resource::<UndoHistory>()
             .add_event::<BuildEvent>()
             .add_event::<DigEvent>()
+            .add_event::<UndoEvent>()
+            .add_systems(Startup, setup_base)
             .add_systems(Update, (
                 handle_input,
                 process_build_events,
                 process_dig_events,
-                update_away_teams,
+                process_undo,
+                (update_away_teams, move_away_teams, mine_deposits).chain(),
+                publish_player_anchors.before(crate::world::stream_chunks),
             ));
     }
 }
@@ -48,3 +55,40 @@ impl PlayerResources {
         }
     }
 }
+
+/// System to keep chunks around structures and away team members streamed in
+fn publish_player_anchors(
+    structures: Query<&Transform, With<PlayerStructure>>,
+    teams: Query<&AwayTeam>,
+    members: Query<&Transform>,
+    mut anchors: ResMut<crate::world::StreamingAnchors>,
+) {
+    let structure_tiles = structures.iter().map(|t| t.translation.as_ivec3());
+    let member_tiles = teams
+        .iter()
+        .flat_map(|team| team.members.iter())
+        .filter_map(|m| members.get(*m).ok())
+        .map(|t| t.translation.as_ivec3());
+    anchors.set(crate::world::AnchorSource::Player, structure_tiles.chain(member_tiles));
+}
+
+/// Keep health (the thing we are defending)
+const KEEP_HP: f32 = 1000.0;
+
+/// Place the player's keep at the centre of the starting area
+fn setup_base(
+    mut commands: Commands,
+    config: Res<crate::world::WorldGenConfig>,
+    world: Res<crate::world::GameWorld>,
+) {
+    use crate::combat::Health;
+
+    let keep_pos = config.spawn_point(world.surface_z);
+    commands.spawn((
+        PlayerStructure::new(StructureKind::Keep),
+        Health::new(KEEP_HP),
+        Transform::from_translation(keep_pos.as_vec3()),
+    ));
+
+    info!("Keep placed at {:?}", keep_pos);
+}
@@ -0,0 +1,131 @@
+//! Undo - take back the player's most recent builds and digs
+//!
+//! Builds are refunded in proportion to the HP left on the tile; digs give
+//! back the tile in exchange for what it yielded. An action can only be
+//! undone while its tile is still as the player left it.
+
+use super::{BuildableType, PlayerResources};
+use crate::world::{ChangeCause, Deposits, GameWorld, Tile, TileChangedEvent, TileYield};
+use bevy::prelude::*;
+
+/// Actions remembered for undo
+const MAX_UNDO: usize = 64;
+
+/// A player action that can be taken back
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerAction {
+    Build {
+        position: IVec3,
+        buildable: BuildableType,
+        replaced: Tile,
+        placed: Tile,
+    },
+    Dig {
+        position: IVec3,
+        dug: Tile,
+        yields: TileYield,
+    },
+}
+
+/// Most recent player actions, newest last
+#[derive(Resource, Default)]
+pub struct UndoHistory {
+    actions: Vec<PlayerAction>,
+}
+
+impl UndoHistory {
+    pub fn push(&mut self, action: PlayerAction) {
+        if self.actions.len() >= MAX_UNDO {
+            self.actions.remove(0);
+        }
+        self.actions.push(action);
+    }
+
+    pub fn clear(&mut self) {
+        self.actions.clear();
+    }
+}
+
+/// Request to undo the most recent action
+#[derive(Event)]
+pub struct UndoEvent;
+
+/// Process undo requests
+pub fn process_undo(
+    mut events: EventReader<UndoEvent>,
+    mut history: ResMut<UndoHistory>,
+    mut world: ResMut<GameWorld>,
+    mut resources: ResMut<PlayerResources>,
+    mut deposits: ResMut<Deposits>,
+    mut tile_events: EventWriter<TileChangedEvent>,
+) {
+    for _ in events.read() {
+        let Some(action) = history.actions.pop() else {
+            info!("Nothing to undo");
+            continue;
+        };
+
+        let (position, restored) = match action {
+            PlayerAction::Build {
+                position,
+                buildable,
+                replaced,
+                placed,
+            } => {
+                // Damaged or not, it must still be what we built
+                let Some(health) = world
+                    .get_tile(position)
+                    .filter(|t| t.kind() == placed.kind())
+                    .map(|t| t.health().unwrap_or(1.0))
+                else {
+                    info!("Cannot undo build at {:?} - tile has changed", position);
+                    continue;
+                };
+
+                let (tungsten, iron, wood) = buildable.cost();
+                let refund = |amount: u32| (amount as f32 * health).floor() as u32;
+                resources.tungsten += refund(tungsten);
+                resources.iron += refund(iron);
+                resources.wood += refund(wood);
+                (position, replaced)
+            }
+            PlayerAction::Dig {
+                position,
+                dug,
+                yields,
+            } => {
+                if !matches!(world.get_tile(position), Some(Tile::Air)) {
+                    info!("Cannot undo dig at {:?} - tile has changed", position);
+                    continue;
+                }
+                if resources.tungsten < yields.tungsten
+                    || resources.iron < yields.iron
+                    || resources.wood < yields.wood
+                {
+                    info!("Cannot undo dig at {:?} - yield already spent", position);
+                    history.actions.push(action);
+                    continue;
+                }
+
+                resources.tungsten -= yields.tungsten;
+                resources.iron -= yields.iron;
+                resources.wood -= yields.wood;
+                // The tile only holds what was given back
+                deposits.set(position, yields);
+                (position, dug)
+            }
+        };
+
+        let old_tile = *world.get_tile(position).unwrap_or(&Tile::Air);
+        world.set_tile(position, restored);
+        tile_events.send(TileChangedEvent {
+            position,
+            old_tile,
+            new_tile: restored,
+            cause: ChangeCause::Undo,
+            actor: None,
+        });
+
+        info!("Undid {:?}", action);
+    }
+}
@@ -2,9 +2,12 @@
 //!
 //! Renders tiles as colored squares in the game window.
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
-use crate::world::{CurrentZLevel, GameWorld, Tile};
+use crate::world::{
+    AnchorSource, CurrentZLevel, FireField, GameWorld, StreamingAnchors, Tile, WaterField, MAX_WATER,
+};
 
 mod camera;
 
@@ -20,6 +23,7 @@ impl Plugin for RenderPlugin {
                 update_tile_sprites,
                 handle_camera_input,
                 update_ui_text,
+                publish_camera_anchor.before(crate::world::stream_chunks),
             ));
     }
 }
@@ -43,9 +47,30 @@ impl Default for RenderSettings {
 /// Marker for tile sprites
 #[derive(Component)]
 pub struct TileSprite {
+    /// Cell in the view grid (0..view_size)
+    pub grid: IVec2,
     pub world_pos: IVec3,
 }
 
+/// World tile under the centre of the screen
+pub fn camera_tile(camera: Vec3, settings: &RenderSettings) -> IVec2 {
+    (camera.truncate() / settings.tile_size).round().as_ivec2() + IVec2::splat(settings.view_size / 2)
+}
+
+/// System to keep the chunks under the camera streamed in
+fn publish_camera_anchor(
+    camera: Query<&Transform, With<Camera2d>>,
+    settings: Res<RenderSettings>,
+    current_z: Res<CurrentZLevel>,
+    mut anchors: ResMut<StreamingAnchors>,
+) {
+    let tile = camera
+        .get_single()
+        .ok()
+        .map(|camera| camera_tile(camera.translation, &settings).extend(current_z.level));
+    anchors.set(AnchorSource::Camera, tile);
+}
+
 /// UI text component
 #[derive(Component)]
 pub struct UiText;
@@ -72,6 +97,7 @@ fn setup_tile_sprites(
                 },
                 Transform::from_xyz(world_x, world_y, 0.0),
                 TileSprite {
+                    grid: IVec2::new(x, y),
                     world_pos: IVec3::new(x, y, 0),
                 },
             ));
@@ -94,20 +120,37 @@ fn setup_tile_sprites(
 }
 
 /// Update tile sprite colors based on world state
+///
+/// The sprite grid follows the camera, so each sprite shows whichever
+/// world tile currently sits under it.
 fn update_tile_sprites(
     world: Res<GameWorld>,
     current_z: Res<CurrentZLevel>,
-    mut sprites: Query<(&mut Sprite, &mut TileSprite)>,
+    settings: Res<RenderSettings>,
+    overlays: Overlays,
+    camera: Query<&Transform, (With<Camera2d>, Without<TileSprite>)>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &mut TileSprite)>,
 ) {
     let z = current_z.level;
+    let half = settings.view_size / 2;
+    let cam_tile = camera
+        .get_single()
+        .map(|t| camera_tile(t.translation, &settings))
+        .unwrap_or(IVec2::splat(half));
+    let origin = cam_tile - IVec2::splat(half);
 
-    for (mut sprite, mut tile_sprite) in sprites.iter_mut() {
-        // Update the Z-level being displayed
-        tile_sprite.world_pos.z = z;
+    for (mut sprite, mut transform, mut tile_sprite) in sprites.iter_mut() {
+        // Update the tile and Z-level being displayed
+        let world_xy = origin + tile_sprite.grid;
+        tile_sprite.world_pos = world_xy.extend(z);
         let pos = tile_sprite.world_pos;
 
+        let screen = (world_xy - IVec2::splat(half)).as_vec2() * settings.tile_size;
+        transform.translation.x = screen.x;
+        transform.translation.y = screen.y;
+
         let color = if let Some(tile) = world.get_tile(pos) {
-            tile_to_color(tile, z)
+            overlays.tint(pos, tile_to_color(tile, z))
         } else {
             // Ungenerated area
             Color::srgb(0.1, 0.1, 0.15)
@@ -117,59 +160,52 @@ fn update_tile_sprites(
     }
 }
 
-/// Convert tile to display color
-fn tile_to_color(tile: &Tile, z: i32) -> Color {
-    match tile {
-        Tile::Air => {
-            if z >= 0 {
-                // Surface air - sky blue
-                Color::srgb(0.4, 0.6, 0.8)
-            } else {
-                // Underground air (tunnel) - dark
-                Color::srgb(0.15, 0.12, 0.1)
-            }
-        }
-        Tile::Dirt { hp, max_hp } => {
-            let health = *hp as f32 / *max_hp as f32;
-            Color::srgb(0.4 * health + 0.2, 0.25 * health + 0.1, 0.1)
-        }
-        Tile::Stone { hp, max_hp } => {
-            let health = *hp as f32 / *max_hp as f32;
-            let gray = 0.3 + 0.3 * health;
-            Color::srgb(gray, gray, gray)
-        }
-        Tile::Wall { hp, max_hp, material } => {
-            let health = *hp as f32 / *max_hp as f32;
-            match material {
-                crate::world::BuildMaterial::Wood => {
-                    Color::srgb(0.5 * health + 0.2, 0.3 * health + 0.1, 0.1)
-                }
-                crate::world::BuildMaterial::Stone => {
-                    Color::srgb(0.5 * health + 0.2, 0.5 * health + 0.2, 0.4 * health + 0.2)
-                }
-                crate::world::BuildMaterial::Metal => {
-                    Color::srgb(0.4 * health + 0.2, 0.4 * health + 0.2, 0.5 * health + 0.3)
-                }
-            }
-        }
-        Tile::Floor { material, .. } => {
-            match material {
-                crate::world::BuildMaterial::Wood => Color::srgb(0.45, 0.3, 0.15),
-                crate::world::BuildMaterial::Stone => Color::srgb(0.4, 0.4, 0.35),
-                crate::world::BuildMaterial::Metal => Color::srgb(0.35, 0.35, 0.4),
-            }
-        }
-        Tile::Rubble => Color::srgb(0.35, 0.3, 0.25),
-        Tile::AntStructure { structure_type, .. } => {
-            match structure_type {
-                crate::world::AntStructureType::Tunnel => Color::srgb(0.3, 0.2, 0.15),
-                crate::world::AntStructureType::Nest => Color::srgb(0.5, 0.2, 0.2),
-                crate::world::AntStructureType::Storage => Color::srgb(0.4, 0.35, 0.2),
-            }
+/// Tint of standing water
+const WATER_COLOR: Color = Color::srgb(0.1, 0.3, 0.8);
+/// Tint of burning tiles
+const FIRE_COLOR: Color = Color::srgb(1.0, 0.45, 0.05);
+
+/// Water and fire drawn over the tiles
+#[derive(SystemParam)]
+struct Overlays<'w> {
+    water: Res<'w, WaterField>,
+    fire: Res<'w, FireField>,
+    time: Res<'w, Time>,
+}
+
+impl Overlays<'_> {
+    /// Tint a tile's color with the water and fire on it
+    fn tint(&self, pos: IVec3, color: Color) -> Color {
+        let color = match self.water.level(pos) {
+            0 => color,
+            // Deeper water shows bluer
+            level => color.mix(&WATER_COLOR, 0.3 + 0.5 * level as f32 / MAX_WATER as f32),
+        };
+        if !self.fire.is_burning(pos) {
+            return color;
         }
+        // Flicker per tile so a blaze doesn't pulse in lockstep
+        let phase = (pos.x * 7 + pos.y * 13) as f32;
+        let flicker = (self.time.elapsed_secs() * 9.0 + phase).sin();
+        color.mix(&FIRE_COLOR, 0.6 + 0.25 * flicker)
     }
 }
 
+/// Convert tile to display color
+fn tile_to_color(tile: &Tile, z: i32) -> Color {
+    let def = tile.def();
+    let (r, g, b) = match (def.underground_color, tile.health(), def.damaged_color) {
+        (Some(underground), _, _) if z < 0 => underground,
+        (_, Some(health), Some(damaged)) => (
+            damaged.0 + (def.color.0 - damaged.0) * health,
+            damaged.1 + (def.color.1 - damaged.1) * health,
+            damaged.2 + (def.color.2 - damaged.2) * health,
+        ),
+        _ => def.color,
+    };
+    Color::srgb(r, g, b)
+}
+
 /// Handle camera movement and Z-level changes
 fn handle_camera_input(
     keyboard: Res<ButtonInput<KeyCode>>,
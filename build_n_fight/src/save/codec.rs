@@ -0,0 +1,320 @@
+//! Binary encoding primitives for save files
+//!
+//! Little-endian, length-prefixed, no padding. Every reader call checks
+//! bounds so a truncated file fails cleanly instead of panicking.
+
+use crate::ai::AntCaste;
+use crate::world::{tile_registry, AntStructureType, BuildMaterial, OreType, Tile};
+use bevy::prelude::*;
+use std::fmt;
+
+/// Entity reference that didn't survive saving
+pub const NO_ENTITY: u32 = u32::MAX;
+
+/// Errors while reading or writing a save
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "I/O error: {}", err),
+            SaveError::BadMagic => write!(f, "not a Build N' Fight save file"),
+            SaveError::UnsupportedVersion(v) => write!(f, "unsupported save version {}", v),
+            SaveError::Corrupt(what) => write!(f, "corrupt save: {}", what),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+/// Append-only byte writer
+#[derive(Default)]
+pub struct Writer {
+    pub bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+
+    pub fn u16(&mut self, v: u16) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, v: u64) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, v: i32) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, v: f32) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+
+    pub fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    pub fn str(&mut self, v: &str) {
+        self.len(v.len());
+        self.bytes.extend_from_slice(v.as_bytes());
+    }
+
+    pub fn ivec3(&mut self, v: IVec3) {
+        self.i32(v.x);
+        self.i32(v.y);
+        self.i32(v.z);
+    }
+
+    pub fn vec3(&mut self, v: Vec3) {
+        self.f32(v.x);
+        self.f32(v.y);
+        self.f32(v.z);
+    }
+
+    pub fn opt_f32(&mut self, v: Option<f32>) {
+        self.bool(v.is_some());
+        if let Some(v) = v {
+            self.f32(v);
+        }
+    }
+
+    pub fn tile(&mut self, tile: &Tile) {
+        match *tile {
+            Tile::Air => self.u8(0),
+            Tile::Dirt { hp, max_hp } => {
+                self.u8(1);
+                self.u16(hp);
+                self.u16(max_hp);
+            }
+            Tile::Stone { hp, max_hp } => {
+                self.u8(2);
+                self.u16(hp);
+                self.u16(max_hp);
+            }
+            Tile::Ore { hp, max_hp, ore } => {
+                self.u8(3);
+                self.u16(hp);
+                self.u16(max_hp);
+                self.u8(ore as u8);
+            }
+            Tile::Wall {
+                hp,
+                max_hp,
+                material,
+            } => {
+                self.u8(4);
+                self.u16(hp);
+                self.u16(max_hp);
+                self.u8(material as u8);
+            }
+            Tile::Floor {
+                hp,
+                max_hp,
+                material,
+            } => {
+                self.u8(5);
+                self.u16(hp);
+                self.u16(max_hp);
+                self.u8(material as u8);
+            }
+            Tile::Rubble => self.u8(6),
+            Tile::AntStructure { hp, structure_type } => {
+                self.u8(7);
+                self.u16(hp);
+                self.u8(structure_type as u8);
+            }
+            Tile::Material { kind, hp } => {
+                // Registry ids can shift when the data file changes, names don't
+                self.u8(8);
+                self.str(&tile_registry().def_or_unknown(kind).name);
+                self.u16(hp);
+            }
+        }
+    }
+
+    pub fn caste(&mut self, caste: AntCaste) {
+        self.u8(caste as u8);
+    }
+}
+
+/// Bounds-checked byte reader
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], SaveError> {
+        let end = self.pos + N;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(SaveError::Corrupt("unexpected end of file"))?;
+        self.pos = end;
+        Ok(slice.try_into().expect("slice has length N"))
+    }
+
+    pub fn u8(&mut self) -> Result<u8, SaveError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, SaveError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, SaveError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, SaveError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, SaveError> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, SaveError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, SaveError> {
+        Ok(self.u8()? != 0)
+    }
+
+    /// Length prefix, sanity-checked against the bytes left
+    pub fn len(&mut self) -> Result<usize, SaveError> {
+        let len = self.u32()? as usize;
+        if len > self.bytes.len() - self.pos {
+            return Err(SaveError::Corrupt("length exceeds file size"));
+        }
+        Ok(len)
+    }
+
+    pub fn str(&mut self) -> Result<String, SaveError> {
+        let len = self.len()?;
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        String::from_utf8(bytes.to_vec()).map_err(|_| SaveError::Corrupt("invalid string"))
+    }
+
+    pub fn ivec3(&mut self) -> Result<IVec3, SaveError> {
+        Ok(IVec3::new(self.i32()?, self.i32()?, self.i32()?))
+    }
+
+    pub fn vec3(&mut self) -> Result<Vec3, SaveError> {
+        Ok(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    pub fn opt_f32(&mut self) -> Result<Option<f32>, SaveError> {
+        Ok(if self.bool()? {
+            Some(self.f32()?)
+        } else {
+            None
+        })
+    }
+
+    pub fn tile(&mut self) -> Result<Tile, SaveError> {
+        Ok(match self.u8()? {
+            0 => Tile::Air,
+            1 => Tile::Dirt {
+                hp: self.u16()?,
+                max_hp: self.u16()?,
+            },
+            2 => Tile::Stone {
+                hp: self.u16()?,
+                max_hp: self.u16()?,
+            },
+            3 => Tile::Ore {
+                hp: self.u16()?,
+                max_hp: self.u16()?,
+                ore: self.ore()?,
+            },
+            4 => Tile::Wall {
+                hp: self.u16()?,
+                max_hp: self.u16()?,
+                material: self.material()?,
+            },
+            5 => Tile::Floor {
+                hp: self.u16()?,
+                max_hp: self.u16()?,
+                material: self.material()?,
+            },
+            6 => Tile::Rubble,
+            7 => Tile::AntStructure {
+                hp: self.u16()?,
+                structure_type: match self.u8()? {
+                    0 => AntStructureType::Tunnel,
+                    1 => AntStructureType::Nest,
+                    2 => AntStructureType::Storage,
+                    _ => return Err(SaveError::Corrupt("ant structure type")),
+                },
+            },
+            8 => {
+                let name = self.str()?;
+                let Some(kind) = tile_registry().kind(&name) else {
+                    return Err(SaveError::Corrupt("unknown tile kind"));
+                };
+                Tile::Material {
+                    kind,
+                    hp: self.u16()?,
+                }
+            }
+            _ => return Err(SaveError::Corrupt("tile tag")),
+        })
+    }
+
+    fn ore(&mut self) -> Result<OreType, SaveError> {
+        match self.u8()? {
+            0 => Ok(OreType::Iron),
+            1 => Ok(OreType::Tungsten),
+            _ => Err(SaveError::Corrupt("ore type")),
+        }
+    }
+
+    fn material(&mut self) -> Result<BuildMaterial, SaveError> {
+        match self.u8()? {
+            0 => Ok(BuildMaterial::Wood),
+            1 => Ok(BuildMaterial::Stone),
+            2 => Ok(BuildMaterial::Metal),
+            _ => Err(SaveError::Corrupt("build material")),
+        }
+    }
+
+    pub fn caste(&mut self) -> Result<AntCaste, SaveError> {
+        match self.u8()? {
+            0 => Ok(AntCaste::Minor),
+            1 => Ok(AntCaste::Median),
+            2 => Ok(AntCaste::Major),
+            3 => Ok(AntCaste::Scout),
+            4 => Ok(AntCaste::Siege),
+            _ => Err(SaveError::Corrupt("ant caste")),
+        }
+    }
+}
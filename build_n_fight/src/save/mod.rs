@@ -0,0 +1,172 @@
+//! Save module - Persisting the full game state
+//!
+//! F5 quicksaves, F9 quickloads, F6 dumps the loaded world as an ASCII map
+//! and F7 the tile change journal for bug reports. Files are a small versioned binary format
+//! (see `codec.rs`); bump `SAVE_VERSION` whenever the layout changes.
+
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+mod codec;
+mod state;
+
+pub use codec::*;
+pub use state::*;
+
+/// File signature at the start of every save
+pub const SAVE_MAGIC: &[u8; 4] = b"BNFS";
+/// Current save layout version
+pub const SAVE_VERSION: u32 = 1;
+/// Where quicksaves go
+pub const QUICKSAVE_PATH: &str = "saves/quicksave.bnf";
+/// Where F6 map dumps go
+pub const MAP_DUMP_PATH: &str = "saves/map_dump.txt";
+/// Where F7 journal dumps go
+pub const JOURNAL_DUMP_PATH: &str = "saves/journal_dump.txt";
+/// How far around each breach the journal dump reviews (in tiles)
+const BREACH_REVIEW_RADIUS: i32 = 6;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveGameEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_systems(Update, (handle_save_input, process_save_requests).chain())
+            .add_systems(Update, (dump_ascii_map, dump_tile_journal));
+    }
+}
+
+/// Request to save the game
+#[derive(Event)]
+pub struct SaveGameEvent {
+    pub path: PathBuf,
+}
+
+/// Request to load a saved game
+#[derive(Event)]
+pub struct LoadGameEvent {
+    pub path: PathBuf,
+}
+
+/// Quicksave / quickload hotkeys
+fn handle_save_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut save_events: EventWriter<SaveGameEvent>,
+    mut load_events: EventWriter<LoadGameEvent>,
+) {
+    if keyboard.just_pressed(KeyCode::F5) {
+        save_events.send(SaveGameEvent {
+            path: QUICKSAVE_PATH.into(),
+        });
+    }
+    if keyboard.just_pressed(KeyCode::F9) {
+        load_events.send(LoadGameEvent {
+            path: QUICKSAVE_PATH.into(),
+        });
+    }
+}
+
+/// Write the loaded world as an ASCII map
+fn dump_ascii_map(keyboard: Res<ButtonInput<KeyCode>>, world: Res<crate::world::GameWorld>) {
+    if !keyboard.just_pressed(KeyCode::F6) {
+        return;
+    }
+    let map = crate::world::export_loaded_ascii_map(&world);
+    match write_file(&PathBuf::from(MAP_DUMP_PATH), map.as_bytes()) {
+        Ok(()) => info!("Dumped ASCII map to {}", MAP_DUMP_PATH),
+        Err(err) => error!("Failed to dump ASCII map: {}", err),
+    }
+}
+
+/// Write the tile change journal, reviewing each breach
+fn dump_tile_journal(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    journal: Res<crate::world::TileJournal>,
+    breach_points: Res<crate::flow::BreachPoints>,
+) {
+    if !keyboard.just_pressed(KeyCode::F7) {
+        return;
+    }
+    let breaches: Vec<IVec3> = breach_points.points.iter().map(|b| b.position).collect();
+    let dump = journal.export(&breaches, BREACH_REVIEW_RADIUS);
+    match write_file(&PathBuf::from(JOURNAL_DUMP_PATH), dump.as_bytes()) {
+        Ok(()) => info!("Dumped tile journal to {}", JOURNAL_DUMP_PATH),
+        Err(err) => error!("Failed to dump tile journal: {}", err),
+    }
+}
+
+/// Exclusive system that performs queued saves and loads
+fn process_save_requests(world: &mut World) {
+    let saves: Vec<SaveGameEvent> = world
+        .resource_mut::<Events<SaveGameEvent>>()
+        .drain()
+        .collect();
+    for event in saves {
+        let bytes = save_game(world);
+        match write_file(&event.path, &bytes) {
+            Ok(()) => info!("Saved game to {:?} ({} bytes)", event.path, bytes.len()),
+            Err(err) => error!("Failed to save {:?}: {}", event.path, err),
+        }
+    }
+
+    let loads: Vec<LoadGameEvent> = world
+        .resource_mut::<Events<LoadGameEvent>>()
+        .drain()
+        .collect();
+    for event in loads {
+        let result = std::fs::read(&event.path)
+            .map_err(SaveError::from)
+            .and_then(|bytes| load_game(world, &bytes));
+        match result {
+            Ok(()) => info!("Loaded game from {:?}", event.path),
+            Err(err) => error!("Failed to load {:?}: {}", event.path, err),
+        }
+    }
+}
+
+/// File signature of a single streamed chunk
+pub const CHUNK_MAGIC: &[u8; 4] = b"BNFC";
+/// Current streamed chunk layout version
+/// - 1: tile runs
+pub const CHUNK_VERSION: u32 = 1;
+
+/// Persist one chunk (used by chunk streaming)
+pub fn write_chunk_file(path: &PathBuf, chunk: &crate::world::Chunk) -> Result<(), SaveError> {
+    let mut w = Writer::default();
+    w.bytes.extend_from_slice(CHUNK_MAGIC);
+    w.u32(CHUNK_VERSION);
+    let runs = chunk.runs();
+    w.len(runs.len());
+    for (count, tile) in &runs {
+        w.u32(*count);
+        w.tile(tile);
+    }
+    write_file(path, &w.bytes)
+}
+
+/// Read back a chunk written by `write_chunk_file`
+pub fn read_chunk_file(path: &PathBuf) -> Result<crate::world::Chunk, SaveError> {
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(CHUNK_MAGIC) {
+        return Err(SaveError::BadMagic);
+    }
+    let mut r = Reader::new(&bytes[CHUNK_MAGIC.len()..]);
+    let version = r.u32()?;
+    if version == 0 || version > CHUNK_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    let mut runs = Vec::new();
+    for _ in 0..r.len()? {
+        runs.push((r.u32()?, r.tile()?));
+    }
+    crate::world::Chunk::from_runs(&runs).ok_or(SaveError::Corrupt("chunk tile count"))
+}
+
+fn write_file(path: &PathBuf, bytes: &[u8]) -> Result<(), SaveError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
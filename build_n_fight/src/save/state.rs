@@ -0,0 +1,1339 @@
+//! Capturing and restoring the full game state
+//!
+//! Saving walks the ECS world and writes each section in a fixed order.
+//! Loading parses the whole file into `SaveData` first, so a bad file never
+//! leaves the game half-loaded, then replaces resources and respawns
+//! entities. Nests are written before anything that refers to them, and
+//! references are stored as indices into the saved nest list. Ants refer
+//! to their leaders (as do tunnel-queued ants and claimed breaches) by
+//! index into the saved ant list, which is written last.
+
+use super::codec::{Reader, SaveError, Writer, NO_ENTITY};
+use super::{SAVE_MAGIC, SAVE_VERSION};
+use crate::ai::{
+    Ant, AntCaste, AntNest, AwarenessState, Discovery, Follower, FollowerState, LeaderState,
+    QueuedAnt, ScentTrails, Scout, SwarmLeader, TunnelNetwork, TunnelSegment,
+};
+use crate::combat::{
+    AmmoType, Health, MountPoint, Projectile, TargetingAngles, Weapon, WeaponCategory,
+};
+use crate::flow::{
+    Breach, BreachPoint, BreachPoints, CostPenalty, PortalGraph, TargetField, TraversalField,
+};
+use crate::player::{AwayTeam, Mission, PlayerResources, PlayerStructure, StructureKind, Supplies, UndoHistory};
+use crate::visibility::{FogOfWar, TileVisibility};
+use crate::world::{
+    Chunk, ChunkLoadedEvent, ChunkStreaming, Deposits, Enclosure, EnvironmentField, FireField,
+    GameWorld, TileJournal, TileYield, WaterField, WorldGenConfig, CHUNK_SIZE, FIRE_COST,
+};
+use bevy::prelude::*;
+
+/// A saved weapon mount
+struct SavedMount {
+    translation: Vec3,
+    mount: MountPoint,
+    weapon: Option<Weapon>,
+}
+
+/// A saved away team member
+struct SavedMember {
+    translation: Vec3,
+    health: Option<Health>,
+}
+
+/// A saved away team
+struct SavedTeam {
+    translation: Option<Vec3>,
+    mission: Mission,
+    supplies: Supplies,
+    cargo: TileYield,
+    members: Vec<SavedMember>,
+}
+
+/// A tunnel-queued ant with its nest and leader as saved indices
+struct SavedQueuedAnt {
+    caste: AntCaste,
+    progress: f32,
+    nest: u32,
+    leader: u32,
+}
+
+/// A leader's state with any ally as a saved unit index
+enum SavedLeaderState {
+    Seeking,
+    Assaulting(IVec3),
+    Reinforcing(u32),
+    Creating(IVec3),
+    Retreating,
+}
+
+/// A saved ant, swarm leader or both, with references as saved indices
+struct SavedUnit {
+    translation: Vec3,
+    /// Caste, HP, max HP and home nest
+    ant: Option<(AntCaste, u16, u16, u32)>,
+    /// Max followers and state
+    leader: Option<(u32, SavedLeaderState)>,
+    /// Leader, formation offset and state
+    follower: Option<(u32, Vec2, FollowerState)>,
+    scout: Option<SavedScout>,
+}
+
+struct SavedScout {
+    nest: u32,
+    discoveries: Vec<Discovery>,
+    returning: bool,
+    home_path: Vec<IVec3>,
+}
+
+/// A breach with its holder as a saved unit index
+struct SavedBreach {
+    position: IVec3,
+    claimed_by: u32,
+    reinforcement_requests: u32,
+    age: f32,
+}
+
+struct SavedSegment {
+    start: IVec3,
+    end: IVec3,
+    move_rate: f32,
+    intact: bool,
+    queue: Vec<SavedQueuedAnt>,
+}
+
+/// Everything parsed from a save file, before touching the ECS world
+struct SaveData {
+    config: WorldGenConfig,
+    surface_z: i32,
+    chunks: Vec<(IVec3, Chunk)>,
+    resources: PlayerResources,
+    nests: Vec<(AntNest, Vec3)>,
+    structures: Vec<(StructureKind, Vec3, Option<Health>)>,
+    mounts: Vec<SavedMount>,
+    teams: Vec<SavedTeam>,
+    segments: Vec<SavedSegment>,
+    scents: Vec<(IVec3, u32, f32)>,
+    breaches: Vec<SavedBreach>,
+    fog_surface_z: i32,
+    fog: Vec<(IVec3, TileVisibility)>,
+    water: Vec<(IVec3, u8)>,
+    fires: Vec<IVec3>,
+    vented: Vec<IVec3>,
+    deposits: Vec<(IVec3, TileYield)>,
+    units: Vec<SavedUnit>,
+}
+
+/// Serialize the whole game state
+pub fn save_game(world: &mut World) -> Vec<u8> {
+    let mut w = Writer::default();
+    w.bytes.extend_from_slice(SAVE_MAGIC);
+    w.u32(SAVE_VERSION);
+
+    // World
+    let config = world.resource::<WorldGenConfig>().clone();
+    w.u64(config.seed);
+    w.i32(config.size.x);
+    w.i32(config.size.y);
+    w.i32(config.depth);
+
+    let game_world = world.resource::<GameWorld>();
+    w.i32(game_world.surface_z);
+    // Chunks streamed out to disk are part of the game too
+    let streaming = world.resource::<ChunkStreaming>();
+    let streamed: Vec<(IVec3, Chunk)> = streaming
+        .persisted()
+        .filter(|pos| !game_world.chunks.contains_key(*pos))
+        .filter_map(|pos| match super::read_chunk_file(&streaming.chunk_path(*pos)) {
+            Ok(chunk) => Some((*pos, chunk)),
+            Err(err) => {
+                warn!("Leaving streamed chunk {:?} out of the save: {}", pos, err);
+                None
+            }
+        })
+        .collect();
+    w.len(game_world.chunks.len() + streamed.len());
+    let streamed = streamed.iter().map(|(pos, chunk)| (pos, chunk));
+    for (pos, chunk) in game_world.chunks.iter().chain(streamed) {
+        w.ivec3(*pos);
+        let runs = chunk.runs();
+        w.len(runs.len());
+        for (count, tile) in &runs {
+            w.u32(*count);
+            w.tile(tile);
+        }
+    }
+
+    let resources = world.resource::<PlayerResources>();
+    w.u32(resources.tungsten);
+    w.u32(resources.iron);
+    w.u32(resources.wood);
+
+    // Nests (the only entities other sections refer to)
+    let mut nest_ids: hashbrown::HashMap<Entity, u32> = hashbrown::HashMap::new();
+    let mut nests = world.query::<(Entity, &AntNest, &Transform)>();
+    let nests: Vec<_> = nests.iter(world).collect();
+    w.len(nests.len());
+    for (index, (entity, nest, transform)) in nests.into_iter().enumerate() {
+        nest_ids.insert(entity, index as u32);
+        w.vec3(transform.translation);
+        w.u32(nest.biomass);
+        w.u8(nest.tech_level);
+        match &nest.awareness {
+            AwarenessState::Unaware => w.u8(0),
+            AwarenessState::Suspicious { last_seen } => {
+                w.u8(1);
+                w.ivec3(*last_seen);
+            }
+            AwarenessState::Aware => w.u8(2),
+            AwarenessState::Aggressive => w.u8(3),
+        }
+        w.len(nest.production_queue.len());
+        for caste in &nest.production_queue {
+            w.caste(*caste);
+        }
+        w.u32(nest.max_population);
+        w.u32(nest.population);
+    }
+    let nest_id = |entity: Entity| *nest_ids.get(&entity).unwrap_or(&NO_ENTITY);
+
+    // Ants, leaders and scouts are written last, but indexed now for the
+    // tunnels
+    let mut units =
+        world.query_filtered::<Entity, Or<(With<Ant>, With<SwarmLeader>, With<Scout>)>>();
+    let units: Vec<Entity> = units.iter(world).collect();
+    let unit_ids: hashbrown::HashMap<Entity, u32> = units
+        .iter()
+        .enumerate()
+        .map(|(index, entity)| (*entity, index as u32))
+        .collect();
+    let unit_id = |entity: Entity| *unit_ids.get(&entity).unwrap_or(&NO_ENTITY);
+
+    // Player structures
+    let mut structures = world.query::<(&PlayerStructure, &Transform, Option<&Health>)>();
+    let structures: Vec<_> = structures.iter(world).collect();
+    w.len(structures.len());
+    for (structure, transform, health) in structures {
+        w.u8(structure.kind as u8);
+        w.vec3(transform.translation);
+        write_health(&mut w, health);
+    }
+
+    // Mount points and their weapons
+    let mut weapons = world.query::<&Weapon>();
+    let mut mounts = world.query::<(&MountPoint, &Transform)>();
+    let mounts: Vec<_> = mounts.iter(world).collect();
+    w.len(mounts.len());
+    for (mount, transform) in mounts {
+        w.vec3(transform.translation);
+        w.len(mount.allowed_categories.len());
+        for category in &mount.allowed_categories {
+            w.u8(*category as u8);
+        }
+        let weapon = mount
+            .mounted_weapon
+            .and_then(|e| weapons.get(world, e).ok());
+        w.bool(weapon.is_some());
+        if let Some(weapon) = weapon {
+            write_weapon(&mut w, weapon);
+        }
+    }
+
+    // Away teams and their members
+    let mut members = world.query::<(&Transform, Option<&Health>)>();
+    let mut teams = world.query::<(&AwayTeam, Option<&Transform>)>();
+    let teams: Vec<_> = teams.iter(world).collect();
+    w.len(teams.len());
+    for (team, transform) in teams {
+        w.bool(transform.is_some());
+        if let Some(transform) = transform {
+            w.vec3(transform.translation);
+        }
+        write_mission(&mut w, &team.mission);
+        w.u32(team.supplies.explosives);
+        w.u32(team.supplies.sealant);
+        w.u32(team.supplies.ammo);
+        w.f32(team.supplies.food);
+        write_yield(&mut w, &team.cargo);
+
+        let saved: Vec<_> = team
+            .members
+            .iter()
+            .filter_map(|e| members.get(world, *e).ok())
+            .collect();
+        w.len(saved.len());
+        for (transform, health) in saved {
+            w.vec3(transform.translation);
+            write_health(&mut w, health);
+        }
+    }
+
+    // Tunnels
+    let network = world.resource::<TunnelNetwork>();
+    w.len(network.segments.len());
+    for segment in &network.segments {
+        w.ivec3(segment.start);
+        w.ivec3(segment.end);
+        w.f32(segment.move_rate);
+        w.bool(segment.intact);
+        let queue: Vec<_> = segment
+            .queue
+            .iter()
+            .filter(|ant| nest_id(ant.nest) != NO_ENTITY)
+            .collect();
+        w.len(queue.len());
+        for ant in queue {
+            w.caste(ant.caste);
+            w.f32(ant.progress);
+            w.u32(nest_id(ant.nest));
+            w.u32(ant.leader.map_or(NO_ENTITY, unit_id));
+        }
+    }
+
+    // Scent
+    let scents: Vec<_> = world
+        .resource::<ScentTrails>()
+        .entries()
+        .filter(|(_, nest, _)| nest_id(*nest) != NO_ENTITY)
+        .collect();
+    w.len(scents.len());
+    for (pos, nest, intensity) in scents {
+        w.ivec3(pos);
+        w.u32(nest_id(nest));
+        w.f32(intensity);
+    }
+
+    // Breaches, with the leaders holding them
+    let breaches = world.resource::<BreachPoints>();
+    w.len(breaches.points.len());
+    for breach in &breaches.points {
+        w.ivec3(breach.position);
+        w.u32(breach.claimed_by.map_or(NO_ENTITY, unit_id));
+        w.u32(breach.reinforcement_requests);
+        w.f32(breach.age);
+    }
+
+    // Fog
+    let fog = world.resource::<FogOfWar>();
+    w.i32(fog.surface_z);
+    let entries: Vec<_> = fog.entries().collect();
+    w.len(entries.len());
+    for (pos, vis) in entries {
+        w.ivec3(pos);
+        w.u8(vis as u8);
+    }
+
+    // Water
+    let water: Vec<_> = world.resource::<WaterField>().entries().collect();
+    w.len(water.len());
+    for (pos, level) in water {
+        w.ivec3(pos);
+        w.u8(level);
+    }
+
+    // Fire
+    let fires: Vec<_> = world.resource::<FireField>().burning().collect();
+    w.len(fires.len());
+    for pos in fires {
+        w.ivec3(pos);
+    }
+
+    // Gas pockets already set off
+    let vented: Vec<_> = world.resource::<EnvironmentField>().vented().collect();
+    w.len(vented.len());
+    for pos in vented {
+        w.ivec3(pos);
+    }
+
+    // Partly mined deposits
+    let deposits: Vec<_> = world.resource::<Deposits>().entries().collect();
+    w.len(deposits.len());
+    for (pos, left) in deposits {
+        w.ivec3(pos);
+        write_yield(&mut w, &left);
+    }
+
+    // Ants, leaders and scouts
+    let mut parts = world.query::<(
+        &Transform,
+        Option<&Ant>,
+        Option<&SwarmLeader>,
+        Option<&Follower>,
+        Option<&Scout>,
+    )>();
+    w.len(units.len());
+    for entity in &units {
+        let Ok((transform, ant, leader, follower, scout)) = parts.get(world, *entity) else {
+            // Every saved unit needs a position; keep the indices intact
+            w.vec3(Vec3::ZERO);
+            w.bool(false);
+            w.bool(false);
+            w.bool(false);
+            w.bool(false);
+            continue;
+        };
+        w.vec3(transform.translation);
+
+        w.bool(ant.is_some());
+        if let Some(ant) = ant {
+            w.caste(ant.caste);
+            w.u16(ant.hp);
+            w.u16(ant.max_hp);
+            w.u32(nest_id(ant.home_nest));
+        }
+
+        w.bool(leader.is_some());
+        if let Some(leader) = leader {
+            w.u32(leader.max_followers);
+            match &leader.state {
+                LeaderState::Seeking => w.u8(0),
+                LeaderState::Assaulting { target } => {
+                    w.u8(1);
+                    w.ivec3(*target);
+                }
+                LeaderState::Reinforcing { ally } => {
+                    w.u8(2);
+                    w.u32(unit_id(*ally));
+                }
+                LeaderState::Creating { target } => {
+                    w.u8(3);
+                    w.ivec3(*target);
+                }
+                LeaderState::Retreating => w.u8(4),
+            }
+        }
+
+        w.bool(follower.is_some());
+        if let Some(follower) = follower {
+            w.u32(unit_id(follower.leader));
+            w.f32(follower.offset.x);
+            w.f32(follower.offset.y);
+            w.u8(match follower.state {
+                FollowerState::Following => 0,
+                FollowerState::Attacking => 1,
+                FollowerState::Dying => 2,
+            });
+        }
+
+        w.bool(scout.is_some());
+        if let Some(scout) = scout {
+            w.u32(nest_id(scout.origin_nest));
+            w.len(scout.discoveries.len());
+            for discovery in &scout.discoveries {
+                write_discovery(&mut w, discovery);
+            }
+            w.bool(scout.returning);
+            w.len(scout.home_path.len());
+            for pos in &scout.home_path {
+                w.ivec3(*pos);
+            }
+        }
+    }
+
+    w.bytes
+}
+
+fn write_discovery(w: &mut Writer, discovery: &Discovery) {
+    match discovery {
+        Discovery::PlayerUnit {
+            position,
+            unit_type,
+        } => {
+            w.u8(0);
+            w.ivec3(*position);
+            w.str(unit_type);
+        }
+        Discovery::PlayerStructure {
+            position,
+            structure_type,
+        } => {
+            w.u8(1);
+            w.ivec3(*position);
+            w.str(structure_type);
+        }
+        Discovery::SealedTunnel { position } => {
+            w.u8(2);
+            w.ivec3(*position);
+        }
+        Discovery::Resource {
+            position,
+            resource_type,
+        } => {
+            w.u8(3);
+            w.ivec3(*position);
+            w.str(resource_type);
+        }
+    }
+}
+
+fn read_discovery(r: &mut Reader) -> Result<Discovery, SaveError> {
+    Ok(match r.u8()? {
+        0 => Discovery::PlayerUnit {
+            position: r.ivec3()?,
+            unit_type: r.str()?,
+        },
+        1 => Discovery::PlayerStructure {
+            position: r.ivec3()?,
+            structure_type: r.str()?,
+        },
+        2 => Discovery::SealedTunnel {
+            position: r.ivec3()?,
+        },
+        3 => Discovery::Resource {
+            position: r.ivec3()?,
+            resource_type: r.str()?,
+        },
+        _ => return Err(SaveError::Corrupt("discovery")),
+    })
+}
+
+fn write_yield(w: &mut Writer, amount: &TileYield) {
+    w.u32(amount.tungsten);
+    w.u32(amount.iron);
+    w.u32(amount.wood);
+}
+
+fn read_yield(r: &mut Reader) -> Result<TileYield, SaveError> {
+    Ok(TileYield {
+        tungsten: r.u32()?,
+        iron: r.u32()?,
+        wood: r.u32()?,
+    })
+}
+
+fn write_health(w: &mut Writer, health: Option<&Health>) {
+    w.bool(health.is_some());
+    if let Some(health) = health {
+        w.f32(health.current);
+        w.f32(health.max);
+    }
+}
+
+fn read_health(r: &mut Reader) -> Result<Option<Health>, SaveError> {
+    if !r.bool()? {
+        return Ok(None);
+    }
+    Ok(Some(Health {
+        current: r.f32()?,
+        max: r.f32()?,
+    }))
+}
+
+fn write_weapon(w: &mut Writer, weapon: &Weapon) {
+    w.u8(weapon.category as u8);
+    w.f32(weapon.damage);
+    w.f32(weapon.range);
+    w.f32(weapon.fire_rate);
+    w.opt_f32(weapon.aoe_radius);
+    w.bool(weapon.targeting.horizontal);
+    w.bool(weapon.targeting.above);
+    w.bool(weapon.targeting.below);
+    w.u8(weapon.ammo_type.map_or(u8::MAX, |a| a as u8));
+    w.u32(weapon.ammo_capacity);
+    w.u32(weapon.current_ammo);
+    w.f32(weapon.cooldown);
+}
+
+fn read_weapon(r: &mut Reader) -> Result<Weapon, SaveError> {
+    Ok(Weapon {
+        category: read_category(r)?,
+        damage: r.f32()?,
+        range: r.f32()?,
+        fire_rate: r.f32()?,
+        aoe_radius: r.opt_f32()?,
+        targeting: TargetingAngles {
+            horizontal: r.bool()?,
+            above: r.bool()?,
+            below: r.bool()?,
+        },
+        ammo_type: match r.u8()? {
+            0 => Some(AmmoType::Bolt),
+            1 => Some(AmmoType::Shell),
+            2 => Some(AmmoType::Fuel),
+            3 => Some(AmmoType::Bomb),
+            u8::MAX => None,
+            _ => return Err(SaveError::Corrupt("ammo type")),
+        },
+        ammo_capacity: r.u32()?,
+        current_ammo: r.u32()?,
+        cooldown: r.f32()?,
+    })
+}
+
+fn read_category(r: &mut Reader) -> Result<WeaponCategory, SaveError> {
+    match r.u8()? {
+        0 => Ok(WeaponCategory::Ballista),
+        1 => Ok(WeaponCategory::Mortar),
+        2 => Ok(WeaponCategory::Flamer),
+        3 => Ok(WeaponCategory::BombDrop),
+        4 => Ok(WeaponCategory::Crossbow),
+        _ => Err(SaveError::Corrupt("weapon category")),
+    }
+}
+
+fn write_mission(w: &mut Writer, mission: &Mission) {
+    match mission {
+        Mission::Scout { target_area } => {
+            w.u8(0);
+            w.ivec3(*target_area);
+        }
+        Mission::Mine { target_deposit } => {
+            w.u8(1);
+            w.ivec3(*target_deposit);
+        }
+        Mission::SealTunnel { tunnel_pos } => {
+            w.u8(2);
+            w.ivec3(*tunnel_pos);
+        }
+        Mission::PlantExplosive { target, fuse_time } => {
+            w.u8(3);
+            w.ivec3(*target);
+            w.f32(*fuse_time);
+        }
+        Mission::Return => w.u8(4),
+    }
+}
+
+fn read_mission(r: &mut Reader) -> Result<Mission, SaveError> {
+    Ok(match r.u8()? {
+        0 => Mission::Scout {
+            target_area: r.ivec3()?,
+        },
+        1 => Mission::Mine {
+            target_deposit: r.ivec3()?,
+        },
+        2 => Mission::SealTunnel {
+            tunnel_pos: r.ivec3()?,
+        },
+        3 => Mission::PlantExplosive {
+            target: r.ivec3()?,
+            fuse_time: r.f32()?,
+        },
+        4 => Mission::Return,
+        _ => return Err(SaveError::Corrupt("mission")),
+    })
+}
+
+/// Parse a save file without touching the game
+fn parse(bytes: &[u8]) -> Result<SaveData, SaveError> {
+    if !bytes.starts_with(SAVE_MAGIC) {
+        return Err(SaveError::BadMagic);
+    }
+    let mut r = Reader::new(&bytes[SAVE_MAGIC.len()..]);
+    let version = r.u32()?;
+    if version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+
+    let config = WorldGenConfig {
+        seed: r.u64()?,
+        size: IVec2::new(r.i32()?, r.i32()?),
+        depth: r.i32()?,
+    };
+    let surface_z = r.i32()?;
+
+    let mut chunks = Vec::new();
+    for _ in 0..r.len()? {
+        let pos = r.ivec3()?;
+        let mut runs = Vec::new();
+        for _ in 0..r.len()? {
+            runs.push((r.u32()?, r.tile()?));
+        }
+        let chunk = Chunk::from_runs(&runs).ok_or(SaveError::Corrupt("chunk tile count"))?;
+        chunks.push((pos, chunk));
+    }
+
+    let resources = PlayerResources {
+        tungsten: r.u32()?,
+        iron: r.u32()?,
+        wood: r.u32()?,
+    };
+
+    let mut nests = Vec::new();
+    for _ in 0..r.len()? {
+        let translation = r.vec3()?;
+        let mut nest = AntNest::new();
+        nest.biomass = r.u32()?;
+        nest.tech_level = r.u8()?;
+        nest.awareness = match r.u8()? {
+            0 => AwarenessState::Unaware,
+            1 => AwarenessState::Suspicious {
+                last_seen: r.ivec3()?,
+            },
+            2 => AwarenessState::Aware,
+            3 => AwarenessState::Aggressive,
+            _ => return Err(SaveError::Corrupt("awareness state")),
+        };
+        for _ in 0..r.len()? {
+            nest.production_queue.push(r.caste()?);
+        }
+        nest.max_population = r.u32()?;
+        nest.population = r.u32()?;
+        nests.push((nest, translation));
+    }
+
+    let mut structures = Vec::new();
+    for _ in 0..r.len()? {
+        let kind = match r.u8()? {
+            0 => StructureKind::Keep,
+            1 => StructureKind::Barracks,
+            2 => StructureKind::Armory,
+            3 => StructureKind::Gate,
+            _ => return Err(SaveError::Corrupt("structure kind")),
+        };
+        structures.push((kind, r.vec3()?, read_health(&mut r)?));
+    }
+
+    let mut mounts = Vec::new();
+    for _ in 0..r.len()? {
+        let translation = r.vec3()?;
+        let mut allowed = Vec::new();
+        for _ in 0..r.len()? {
+            allowed.push(read_category(&mut r)?);
+        }
+        let weapon = if r.bool()? {
+            Some(read_weapon(&mut r)?)
+        } else {
+            None
+        };
+        mounts.push(SavedMount {
+            translation,
+            mount: MountPoint::new(allowed),
+            weapon,
+        });
+    }
+
+    let mut teams = Vec::new();
+    for _ in 0..r.len()? {
+        let translation = if r.bool()? { Some(r.vec3()?) } else { None };
+        let mission = read_mission(&mut r)?;
+        let supplies = Supplies {
+            explosives: r.u32()?,
+            sealant: r.u32()?,
+            ammo: r.u32()?,
+            food: r.f32()?,
+        };
+        let cargo = read_yield(&mut r)?;
+        let mut members = Vec::new();
+        for _ in 0..r.len()? {
+            members.push(SavedMember {
+                translation: r.vec3()?,
+                health: read_health(&mut r)?,
+            });
+        }
+        teams.push(SavedTeam {
+            translation,
+            mission,
+            supplies,
+            cargo,
+            members,
+        });
+    }
+
+    let check_nest = |id: u32| {
+        if (id as usize) < nests.len() {
+            Ok(id)
+        } else {
+            Err(SaveError::Corrupt("nest reference"))
+        }
+    };
+
+    let mut segments = Vec::new();
+    for _ in 0..r.len()? {
+        let start = r.ivec3()?;
+        let end = r.ivec3()?;
+        let move_rate = r.f32()?;
+        let intact = r.bool()?;
+        let mut queue = Vec::new();
+        for _ in 0..r.len()? {
+            queue.push(SavedQueuedAnt {
+                caste: r.caste()?,
+                progress: r.f32()?,
+                nest: check_nest(r.u32()?)?,
+                leader: r.u32()?,
+            });
+        }
+        segments.push(SavedSegment {
+            start,
+            end,
+            move_rate,
+            intact,
+            queue,
+        });
+    }
+
+    let mut scents = Vec::new();
+    for _ in 0..r.len()? {
+        scents.push((r.ivec3()?, check_nest(r.u32()?)?, r.f32()?));
+    }
+
+    let mut breaches = Vec::new();
+    for _ in 0..r.len()? {
+        breaches.push(SavedBreach {
+            position: r.ivec3()?,
+            claimed_by: r.u32()?,
+            reinforcement_requests: r.u32()?,
+            age: r.f32()?,
+        });
+    }
+
+    let fog_surface_z = r.i32()?;
+    let mut fog = Vec::new();
+    for _ in 0..r.len()? {
+        let pos = r.ivec3()?;
+        let vis = match r.u8()? {
+            0 => TileVisibility::Unknown,
+            1 => TileVisibility::Revealed,
+            2 => TileVisibility::Visible,
+            3 => TileVisibility::SonarContact,
+            _ => return Err(SaveError::Corrupt("tile visibility")),
+        };
+        fog.push((pos, vis));
+    }
+
+    let mut water = Vec::new();
+    for _ in 0..r.len()? {
+        water.push((r.ivec3()?, r.u8()?));
+    }
+
+    let mut fires = Vec::new();
+    for _ in 0..r.len()? {
+        fires.push(r.ivec3()?);
+    }
+
+    let mut vented = Vec::new();
+    for _ in 0..r.len()? {
+        vented.push(r.ivec3()?);
+    }
+
+    let mut deposits = Vec::new();
+    for _ in 0..r.len()? {
+        deposits.push((r.ivec3()?, read_yield(&mut r)?));
+    }
+
+    let mut units = Vec::new();
+    for _ in 0..r.len()? {
+        let translation = r.vec3()?;
+        let ant = if r.bool()? {
+            Some((r.caste()?, r.u16()?, r.u16()?, r.u32()?))
+        } else {
+            None
+        };
+        let leader = if r.bool()? {
+            let max_followers = r.u32()?;
+            let state = match r.u8()? {
+                0 => SavedLeaderState::Seeking,
+                1 => SavedLeaderState::Assaulting(r.ivec3()?),
+                2 => SavedLeaderState::Reinforcing(r.u32()?),
+                3 => SavedLeaderState::Creating(r.ivec3()?),
+                4 => SavedLeaderState::Retreating,
+                _ => return Err(SaveError::Corrupt("leader state")),
+            };
+            Some((max_followers, state))
+        } else {
+            None
+        };
+        let follower = if r.bool()? {
+            let leader = r.u32()?;
+            let offset = Vec2::new(r.f32()?, r.f32()?);
+            let state = match r.u8()? {
+                0 => FollowerState::Following,
+                1 => FollowerState::Attacking,
+                2 => FollowerState::Dying,
+                _ => return Err(SaveError::Corrupt("follower state")),
+            };
+            Some((leader, offset, state))
+        } else {
+            None
+        };
+        let scout = if r.bool()? {
+            let nest = r.u32()?;
+            let mut discoveries = Vec::new();
+            for _ in 0..r.len()? {
+                discoveries.push(read_discovery(&mut r)?);
+            }
+            let returning = r.bool()?;
+            let mut home_path = Vec::new();
+            for _ in 0..r.len()? {
+                home_path.push(r.ivec3()?);
+            }
+            Some(SavedScout {
+                nest,
+                discoveries,
+                returning,
+                home_path,
+            })
+        } else {
+            None
+        };
+        units.push(SavedUnit {
+            translation,
+            ant,
+            leader,
+            follower,
+            scout,
+        });
+    }
+
+    Ok(SaveData {
+        config,
+        surface_z,
+        chunks,
+        resources,
+        nests,
+        structures,
+        mounts,
+        teams,
+        segments,
+        scents,
+        breaches,
+        fog_surface_z,
+        fog,
+        water,
+        fires,
+        vented,
+        deposits,
+        units,
+    })
+}
+
+/// Replace the current game with a saved one
+pub fn load_game(world: &mut World, bytes: &[u8]) -> Result<(), SaveError> {
+    let data = parse(bytes)?;
+
+    despawn_game_entities(world);
+
+    // World and derived navigation data
+    *world.resource_mut::<WorldGenConfig>() = data.config;
+    // Streamed chunks on disk belong to the game being replaced
+    world.resource_mut::<ChunkStreaming>().reset();
+    {
+        let mut game_world = world.resource_mut::<GameWorld>();
+        game_world.surface_z = data.surface_z;
+        game_world.chunks = data.chunks.into_iter().collect();
+    }
+    world.resource_mut::<TraversalField>().clear_penalties();
+    world.resource_mut::<Enclosure>().dirty = true;
+    {
+        let chunk_keys: Vec<IVec3> = world
+            .resource::<GameWorld>()
+            .chunks
+            .keys()
+            .copied()
+            .collect();
+        let mut graph = world.resource_mut::<PortalGraph>();
+        graph.chunks.clear();
+        for chunk in &chunk_keys {
+            graph.mark_dirty(*chunk);
+        }
+        world.send_event_batch(chunk_keys.into_iter().map(|chunk| ChunkLoadedEvent { chunk }));
+    }
+    rebuild_damaged_walls(world);
+
+    *world.resource_mut::<PlayerResources>() = data.resources;
+    // History from before the load no longer matches the world
+    world.resource_mut::<UndoHistory>().clear();
+    world.resource_mut::<TileJournal>().clear();
+
+    // Entities - nests first so references can be remapped
+    let nests: Vec<Entity> = data
+        .nests
+        .into_iter()
+        .map(|(nest, translation)| {
+            world
+                .spawn((nest, Transform::from_translation(translation)))
+                .id()
+        })
+        .collect();
+
+    for (kind, translation, health) in data.structures {
+        let mut entity = world.spawn((
+            PlayerStructure::new(kind),
+            Transform::from_translation(translation),
+        ));
+        if let Some(health) = health {
+            entity.insert(health);
+        }
+    }
+
+    for saved in data.mounts {
+        let mut mount = saved.mount;
+        let mount_entity = world
+            .spawn(Transform::from_translation(saved.translation))
+            .id();
+        if let Some(weapon) = saved.weapon {
+            let weapon_entity = world
+                .spawn((weapon, Transform::default()))
+                .set_parent(mount_entity)
+                .id();
+            mount.mounted_weapon = Some(weapon_entity);
+        }
+        world.entity_mut(mount_entity).insert(mount);
+    }
+
+    // Ants and leaders - spawn them all first so references can be remapped.
+    // Missing nests and leaders become placeholders; followers of a missing
+    // leader are picked up by succession.
+    let units: Vec<Entity> = data
+        .units
+        .iter()
+        .map(|unit| world.spawn(Transform::from_translation(unit.translation)).id())
+        .collect();
+    let nest = |id: u32| nests.get(id as usize).copied().unwrap_or(Entity::PLACEHOLDER);
+    let unit = |id: u32| units.get(id as usize).copied().unwrap_or(Entity::PLACEHOLDER);
+    for (entity, saved) in units.iter().zip(data.units) {
+        let mut entity = world.entity_mut(*entity);
+        if let Some((caste, hp, max_hp, home)) = saved.ant {
+            let mut ant = Ant::new(caste, nest(home));
+            ant.hp = hp;
+            ant.max_hp = max_hp;
+            entity.insert(ant);
+        }
+        if let Some((max_followers, state)) = saved.leader {
+            let mut leader = SwarmLeader::new(max_followers);
+            leader.state = match state {
+                SavedLeaderState::Seeking => LeaderState::Seeking,
+                SavedLeaderState::Assaulting(target) => LeaderState::Assaulting { target },
+                SavedLeaderState::Reinforcing(ally) => {
+                    LeaderState::Reinforcing { ally: unit(ally) }
+                }
+                SavedLeaderState::Creating(target) => LeaderState::Creating { target },
+                SavedLeaderState::Retreating => LeaderState::Retreating,
+            };
+            entity.insert(leader);
+        }
+        if let Some((leader, offset, state)) = saved.follower {
+            let mut follower = Follower::new(unit(leader), offset);
+            follower.state = state;
+            entity.insert(follower);
+        }
+        if let Some(saved) = saved.scout {
+            let mut scout = Scout::new(nest(saved.nest));
+            scout.discoveries = saved.discoveries;
+            scout.returning = saved.returning;
+            scout.home_path = saved.home_path;
+            entity.insert(scout);
+        }
+    }
+
+    for saved in data.teams {
+        let members = saved
+            .members
+            .into_iter()
+            .map(|member| {
+                let mut entity = world.spawn(Transform::from_translation(member.translation));
+                if let Some(health) = member.health {
+                    entity.insert(health);
+                }
+                entity.id()
+            })
+            .collect();
+        let mut team = AwayTeam::new(members, saved.mission).with_supplies(saved.supplies);
+        team.cargo = saved.cargo;
+        let mut entity = world.spawn(team);
+        if let Some(translation) = saved.translation {
+            entity.insert(Transform::from_translation(translation));
+        }
+    }
+
+    // Resources that refer to nests
+    {
+        let mut network = world.resource_mut::<TunnelNetwork>();
+        network.segments = data
+            .segments
+            .into_iter()
+            .map(|saved| {
+                let mut segment = TunnelSegment::new(saved.start, saved.end, saved.move_rate);
+                segment.intact = saved.intact;
+                segment.queue = saved
+                    .queue
+                    .into_iter()
+                    .map(|ant| QueuedAnt {
+                        caste: ant.caste,
+                        progress: ant.progress,
+                        leader: units.get(ant.leader as usize).copied(),
+                        nest: nests[ant.nest as usize],
+                    })
+                    .collect();
+                segment
+            })
+            .collect();
+    }
+    {
+        let mut trails = world.resource_mut::<ScentTrails>();
+        trails.clear();
+        for (pos, nest, intensity) in data.scents {
+            trails.add_scent(pos, nests[nest as usize], intensity);
+        }
+    }
+
+    // Claims only stand if the holder came back as a leader
+    let mut breaches = Vec::new();
+    for saved in data.breaches {
+        let mut breach = BreachPoint::new(world.spawn(Breach).id(), saved.position);
+        breach.age = saved.age;
+        let holder = units.get(saved.claimed_by as usize).copied();
+        if let Some(mut leader) = holder.and_then(|e| world.get_mut::<SwarmLeader>(e)) {
+            leader.claimed_breach = Some(breach.entity);
+            breach.claimed_by = holder;
+            breach.reinforcement_requests = saved.reinforcement_requests;
+        }
+        breaches.push(breach);
+    }
+    world.resource_mut::<BreachPoints>().points = breaches;
+
+    {
+        let mut fog = world.resource_mut::<FogOfWar>();
+        fog.clear();
+        fog.surface_z = data.fog_surface_z;
+        for (pos, vis) in data.fog {
+            fog.set(pos, vis);
+        }
+    }
+
+    {
+        let mut water = world.resource_mut::<WaterField>();
+        water.clear();
+        for (pos, level) in data.water {
+            water.set_level(pos, level);
+        }
+    }
+
+    world.resource_scope(|world, mut fire: Mut<FireField>| {
+        fire.clear();
+        let lit: Vec<IVec3> = data
+            .fires
+            .into_iter()
+            .filter(|pos| fire.ignite(world.resource::<GameWorld>(), *pos))
+            .collect();
+        let mut traversal = world.resource_mut::<TraversalField>();
+        for pos in lit {
+            traversal.set_penalty(CostPenalty::Fire, pos, FIRE_COST);
+        }
+    });
+
+    {
+        let mut environment = world.resource_mut::<EnvironmentField>();
+        environment.clear();
+        for pos in data.vented {
+            environment.vent(pos);
+        }
+    }
+
+    {
+        let mut deposits = world.resource_mut::<Deposits>();
+        deposits.clear();
+        for (pos, left) in data.deposits {
+            deposits.set(pos, left);
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove every entity that a save replaces
+fn despawn_game_entities(world: &mut World) {
+    let mut doomed: Vec<Entity> = Vec::new();
+
+    let mut team_members = world.query::<&AwayTeam>();
+    for team in team_members.iter(world) {
+        doomed.extend(team.members.iter().copied());
+    }
+
+    macro_rules! collect_with {
+        ($($component:ty),*) => {
+            $(
+                let mut query = world.query_filtered::<Entity, With<$component>>();
+                doomed.extend(query.iter(world));
+            )*
+        };
+    }
+    collect_with!(
+        AntNest,
+        Ant,
+        SwarmLeader,
+        Scout,
+        PlayerStructure,
+        MountPoint,
+        Weapon,
+        AwayTeam,
+        Projectile,
+        Breach
+    );
+
+    for entity in doomed {
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+}
+
+/// Damaged walls are tracked from tile events; re-derive them after a load
+fn rebuild_damaged_walls(world: &mut World) {
+    let size = CHUNK_SIZE as i32;
+    let damaged: hashbrown::HashMap<IVec3, u32> = world
+        .resource::<GameWorld>()
+        .chunks
+        .iter()
+        .flat_map(|(chunk_pos, chunk)| {
+            chunk.iter_tiles().filter_map(move |(local, tile)| {
+                let value = crate::flow::damaged_wall_value(tile);
+                (value > 0).then(|| (*chunk_pos * size + local.as_ivec3(), value))
+            })
+        })
+        .collect();
+
+    let mut target_field = world.resource_mut::<TargetField>();
+    target_field.damaged_walls = damaged;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{Region, Tile, TileKind};
+
+    /// An ECS world with every resource saving and loading touch
+    fn empty_game(name: &str) -> World {
+        let mut world = World::new();
+        world.init_resource::<WorldGenConfig>();
+        world.init_resource::<GameWorld>();
+        let mut streaming = ChunkStreaming::default();
+        streaming.directory = std::env::temp_dir().join(name);
+        world.insert_resource(streaming);
+        world.init_resource::<PlayerResources>();
+        world.init_resource::<TunnelNetwork>();
+        world.init_resource::<ScentTrails>();
+        world.init_resource::<BreachPoints>();
+        world.init_resource::<FogOfWar>();
+        world.init_resource::<WaterField>();
+        world.init_resource::<FireField>();
+        world.init_resource::<EnvironmentField>();
+        world.init_resource::<Deposits>();
+        world.init_resource::<TraversalField>();
+        world.init_resource::<Enclosure>();
+        world.init_resource::<PortalGraph>();
+        world.init_resource::<TargetField>();
+        world.init_resource::<UndoHistory>();
+        world.init_resource::<TileJournal>();
+        world.init_resource::<Events<ChunkLoadedEvent>>();
+        world
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut source = empty_game("bnf_round_trip_source");
+        let wood = IVec3::new(3, 2, -1);
+        let ore = IVec3::new(-20, 5, -4);
+        {
+            let mut game_world = source.resource_mut::<GameWorld>();
+            game_world.fill_region(Region::chunk(IVec3::ZERO), Tile::from_kind(TileKind::DIRT));
+            game_world.set_tile(wood, Tile::from_kind(TileKind::WOOD_WALL));
+            game_world.set_tile(ore, Tile::from_kind(TileKind::IRON_ORE));
+        }
+
+        let nest = source.spawn((AntNest::new(), Transform::default())).id();
+        let breach = source.spawn(Breach).id();
+        let mut holder = SwarmLeader::new(10);
+        holder.claimed_breach = Some(breach);
+        holder.state = LeaderState::Assaulting { target: wood };
+        let holder = source
+            .spawn((Ant::new(AntCaste::Major, nest), holder, Transform::default()))
+            .id();
+        let mut helper = SwarmLeader::new(8);
+        helper.state = LeaderState::Reinforcing { ally: holder };
+        let helper = source.spawn((helper, Transform::default())).id();
+        for leader in [holder, holder, helper] {
+            source.spawn((
+                Ant::new(AntCaste::Minor, nest),
+                Follower::new(leader, Vec2::ONE),
+                Transform::default(),
+            ));
+        }
+        {
+            let mut point = BreachPoint::new(breach, wood);
+            point.claimed_by = Some(holder);
+            point.request_reinforcements(3);
+            source.resource_mut::<BreachPoints>().points.push(point);
+        }
+        let mut segment = TunnelSegment::new(IVec3::ZERO, IVec3::X * 8, 1.0);
+        segment.queue.push(QueuedAnt {
+            caste: AntCaste::Median,
+            progress: 0.5,
+            leader: Some(holder),
+            nest,
+        });
+        source.resource_mut::<TunnelNetwork>().segments.push(segment);
+
+        let wet = IVec3::new(1, 1, 1);
+        source.resource_mut::<WaterField>().set_level(wet, 5);
+        source.resource_scope(|world, mut fire: Mut<FireField>| {
+            assert!(fire.ignite(world.resource::<GameWorld>(), wood));
+        });
+        let left = TileYield {
+            iron: 7,
+            ..default()
+        };
+        source.resource_mut::<Deposits>().set(ore, left);
+
+        let bytes = save_game(&mut source);
+        let mut loaded = empty_game("bnf_round_trip_loaded");
+        load_game(&mut loaded, &bytes).unwrap();
+
+        // Chunks
+        let tiles = |world: &World| {
+            let game_world = world.resource::<GameWorld>();
+            let mut chunks: Vec<IVec3> = game_world.chunks.keys().copied().collect();
+            chunks.sort_by_key(|c| (c.x, c.y, c.z));
+            chunks
+                .into_iter()
+                .flat_map(|c| game_world.tiles_in(Region::chunk(c)).map(|(p, t)| (p, *t)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tiles(&source), tiles(&loaded));
+
+        // Ants, leaders and followers point at their new entities
+        let mut leaders = loaded.query::<(Entity, &SwarmLeader)>();
+        let leaders: Vec<(Entity, LeaderState, Option<Entity>)> = leaders
+            .iter(&loaded)
+            .map(|(entity, leader)| (entity, leader.state.clone(), leader.claimed_breach))
+            .collect();
+        assert_eq!(leaders.len(), 2);
+        let (holder, _, claimed) = *leaders
+            .iter()
+            .find(|(_, state, _)| *state == LeaderState::Assaulting { target: wood })
+            .unwrap();
+        let (helper, ..) = *leaders
+            .iter()
+            .find(|(_, state, _)| *state == LeaderState::Reinforcing { ally: holder })
+            .unwrap();
+        let mut nests = loaded.query_filtered::<Entity, With<AntNest>>();
+        let nest = nests.single(&loaded);
+        let mut followers = loaded.query::<(&Follower, &Ant)>();
+        let mut led: Vec<Entity> = followers
+            .iter(&loaded)
+            .map(|(follower, ant)| {
+                assert_eq!(ant.home_nest, nest);
+                follower.leader
+            })
+            .collect();
+        led.sort();
+        let mut expected = vec![holder, holder, helper];
+        expected.sort();
+        assert_eq!(led, expected);
+        let queued = &loaded.resource::<TunnelNetwork>().segments[0].queue[0];
+        assert_eq!((queued.leader, queued.nest), (Some(holder), nest));
+
+        // The breach claim survives on both sides
+        let points = loaded.resource::<BreachPoints>();
+        let point = points.at_position(wood).unwrap();
+        assert_eq!(claimed, Some(point.entity));
+        assert_eq!(point.claimed_by, Some(holder));
+        assert_eq!(point.reinforcement_requests, 3);
+
+        // Water, fire and deposits
+        assert_eq!(loaded.resource::<WaterField>().level(wet), 5);
+        assert!(loaded.resource::<FireField>().is_burning(wood));
+        let deposits: Vec<_> = loaded.resource::<Deposits>().entries().collect();
+        assert_eq!(deposits, vec![(ore, left)]);
+    }
+}
@@ -40,6 +40,16 @@ impl FogOfWar {
         self.visibility.insert(pos, visibility);
     }
 
+    /// Iterate every explicitly stored visibility entry
+    pub fn entries(&self) -> impl Iterator<Item = (IVec3, TileVisibility)> + '_ {
+        self.visibility.iter().map(|(pos, vis)| (*pos, *vis))
+    }
+
+    /// Forget everything that was explored
+    pub fn clear(&mut self) {
+        self.visibility.clear();
+    }
+
     /// Reveal tiles around a position
     pub fn reveal_around(&mut self, center: IVec3, radius: i32) {
         for x in -radius..=radius {
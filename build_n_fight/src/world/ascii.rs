@@ -0,0 +1,262 @@
+//! ASCII maps - dump Z-levels as text grids and parse them back
+//!
+//! Used for hand-authored test maps, bug reports and snapshots. Each level
+//! is a block of rows using the tile registry glyphs (`Tile::to_ascii`),
+//! north at the top. Lines starting with `@` are directives:
+//!
+//! ```text
+//! @surface 0
+//! @origin 0 15        world x, y of the top-left glyph
+//! @level 0            following rows belong to this Z-level
+//! ....H....
+//! @level -1
+//! ,,,,#,,,,
+//! @hp 4 15 -1 35/100  tile HP (current/max) where not at full health
+//! @# anything         comment
+//! ```
+//!
+//! A space (or a short or blank row) leaves tiles unset. Only natural
+//! ground, walls and floors can have a max HP other than their kind's.
+
+use super::{tile_registry, GameWorld, Tile};
+use bevy::prelude::*;
+use std::fmt;
+
+/// Problem parsing an ASCII map
+#[derive(Debug)]
+pub struct AsciiMapError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsciiMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Dump the inclusive box `min..=max` of the world, top level first
+///
+/// Unloaded tiles are written as spaces.
+pub fn export_ascii_map(world: &GameWorld, min: IVec3, max: IVec3) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("@surface {}\n", world.surface_z));
+    out.push_str(&format!("@origin {} {}\n", min.x, max.y));
+
+    let mut damaged = Vec::new();
+    for z in (min.z..=max.z).rev() {
+        out.push_str(&format!("@level {}\n", z));
+        for y in (min.y..=max.y).rev() {
+            let row: String = (min.x..=max.x)
+                .map(|x| {
+                    let pos = IVec3::new(x, y, z);
+                    match world.get_tile(pos) {
+                        Some(tile) => {
+                            if tile.health().is_some_and(|h| h < 1.0) {
+                                damaged.push((pos, *tile));
+                            }
+                            tile.to_ascii()
+                        }
+                        None => ' ',
+                    }
+                })
+                .collect();
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+    }
+
+    for (pos, tile) in damaged {
+        let hp = tile.hp().unwrap_or(0);
+        out.push_str(&format!(
+            "@hp {} {} {} {}/{}\n",
+            pos.x,
+            pos.y,
+            pos.z,
+            hp,
+            max_hp(&tile)
+        ));
+    }
+    out
+}
+
+/// Dump every loaded chunk
+pub fn export_loaded_ascii_map(world: &GameWorld) -> String {
+    match world.bounds() {
+        Some((min, max)) => export_ascii_map(world, min, max),
+        None => format!("@surface {}\n", world.surface_z),
+    }
+}
+
+/// Parse a map written by `export_ascii_map` (or by hand)
+pub fn import_ascii_map(text: &str) -> Result<GameWorld, AsciiMapError> {
+    let registry = tile_registry();
+    let mut world = GameWorld::new();
+    let mut origin = IVec2::ZERO;
+    let mut level: Option<(i32, i32)> = None; // (z, rows read)
+
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let err = |message: String| AsciiMapError {
+            line: line_no,
+            message,
+        };
+
+        if let Some(directive) = line.strip_prefix('@') {
+            let mut parts = directive.split_whitespace();
+            let keyword = parts.next().unwrap_or("");
+            let args: Vec<&str> = parts.collect();
+            let int = |i: usize| -> Result<i32, AsciiMapError> {
+                args.get(i).and_then(|a| a.parse().ok()).ok_or_else(|| {
+                    err(format!(
+                        "@{} expects an integer argument {}",
+                        keyword,
+                        i + 1
+                    ))
+                })
+            };
+
+            match keyword {
+                "surface" => world.surface_z = int(0)?,
+                "origin" => origin = IVec2::new(int(0)?, int(1)?),
+                "level" => level = Some((int(0)?, 0)),
+                "hp" => {
+                    let pos = IVec3::new(int(0)?, int(1)?, int(2)?);
+                    let (hp, max) = parse_hp(args.get(3).copied())
+                        .ok_or_else(|| err("@hp expects HP as current or current/max".into()))?;
+                    let tile = world
+                        .get_tile(pos)
+                        .copied()
+                        .ok_or_else(|| err(format!("@hp for unset tile {:?}", pos)))?;
+                    let tile = with_hp(tile, hp, max)
+                        .map_err(|message| err(format!("tile at {:?} {}", pos, message)))?;
+                    world.set_tile(pos, tile);
+                }
+                _ if keyword.starts_with('#') => {}
+                _ => return Err(err(format!("unknown directive @{}", keyword))),
+            }
+            continue;
+        }
+
+        // Blank lines are rows of unset tiles once a level has started
+        let Some((z, row)) = level.as_mut() else {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Err(err("map row before any @level".into()));
+        };
+        let y = origin.y - *row;
+        for (dx, glyph) in line.chars().enumerate() {
+            if glyph == ' ' {
+                continue;
+            }
+            let kind = registry
+                .kind_by_glyph(glyph)
+                .ok_or_else(|| err(format!("unknown tile glyph '{}'", glyph)))?;
+            world.set_tile(
+                IVec3::new(origin.x + dx as i32, y, *z),
+                Tile::from_kind(kind),
+            );
+        }
+        *row += 1;
+    }
+
+    for chunk in world.chunks.values_mut() {
+        chunk.compact();
+    }
+    Ok(world)
+}
+
+/// Full HP of a tile as written in `@hp` annotations
+fn max_hp(tile: &Tile) -> u16 {
+    match tile {
+        Tile::Dirt { max_hp, .. }
+        | Tile::Stone { max_hp, .. }
+        | Tile::Ore { max_hp, .. }
+        | Tile::Wall { max_hp, .. }
+        | Tile::Floor { max_hp, .. } => *max_hp,
+        _ => tile.def().hp,
+    }
+}
+
+/// Parse `current` or `current/max`
+fn parse_hp(arg: Option<&str>) -> Option<(u16, Option<u16>)> {
+    let arg = arg?;
+    match arg.split_once('/') {
+        Some((hp, max)) => Some((hp.parse().ok()?, Some(max.parse().ok()?))),
+        None => Some((arg.parse().ok()?, None)),
+    }
+}
+
+/// The same tile with its HP (and optionally max HP) replaced
+fn with_hp(mut tile: Tile, new_hp: u16, new_max: Option<u16>) -> Result<Tile, String> {
+    let kind_max = tile.def().hp;
+    match &mut tile {
+        Tile::Dirt { hp, max_hp }
+        | Tile::Stone { hp, max_hp }
+        | Tile::Ore { hp, max_hp, .. }
+        | Tile::Wall { hp, max_hp, .. }
+        | Tile::Floor { hp, max_hp, .. } => {
+            *hp = new_hp;
+            if let Some(max) = new_max {
+                *max_hp = max;
+            }
+        }
+        Tile::AntStructure { hp, .. } | Tile::Material { hp, .. } => {
+            // No room to store a max of their own
+            if let Some(max) = new_max.filter(|max| *max != kind_max) {
+                return Err(format!("has a fixed max HP of {}, not {}", kind_max, max));
+            }
+            *hp = new_hp;
+        }
+        _ => return Err("has no HP".into()),
+    }
+    Ok(tile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::TileKind;
+
+    #[test]
+    fn damaged_tiles_survive_a_round_trip() {
+        let mut world = GameWorld::new();
+        for x in 0..4 {
+            for y in 0..3 {
+                world.set_tile(IVec3::new(x, y, 0), Tile::Air);
+                world.set_tile(IVec3::new(x, y, -1), Tile::from_kind(TileKind::DIRT));
+            }
+        }
+        let clay = tile_registry().kind_by_glyph('c').expect("clay is registered");
+        let damaged = [
+            (IVec3::new(0, 0, 0), TileKind::STONE_WALL, 40, Some(250)),
+            (IVec3::new(1, 1, 0), clay, 3, None),
+            (IVec3::new(2, 2, 0), TileKind::ANT_NEST, 1, None),
+            (IVec3::new(3, 0, -1), TileKind::DIRT, 2, Some(7)),
+        ];
+        for (pos, kind, hp, max) in damaged {
+            let tile = with_hp(Tile::from_kind(kind), hp, max).unwrap();
+            world.set_tile(pos, tile);
+        }
+
+        let (min, max) = (IVec3::new(0, 0, -1), IVec3::new(3, 2, 0));
+        let text = export_ascii_map(&world, min, max);
+        assert_eq!(text.matches("@hp").count(), damaged.len());
+
+        let imported = import_ascii_map(&text).unwrap();
+        for pos in crate::world::Region::cuboid(min, max).positions() {
+            assert_eq!(imported.get_tile(pos), world.get_tile(pos), "at {:?}", pos);
+        }
+        assert_eq!(export_ascii_map(&imported, min, max), text);
+    }
+
+    #[test]
+    fn fixed_max_hp_is_not_dropped() {
+        let text = "@level 0\nO\n@hp 0 0 0 1/999\n";
+        let Err(err) = import_ascii_map(text) else {
+            panic!("a custom max HP on an ant nest should not import");
+        };
+        assert_eq!(err.line, 3);
+    }
+}
@@ -1,14 +1,39 @@
 //! Chunk-based world storage for efficient tile access
+//!
+//! Chunks are palette-compressed: a chunk of one tile stores just that
+//! tile, a chunk of a few kinds stores a small palette plus one byte per
+//! position. Only chunks with more than 256 distinct tiles (lots of
+//! differently damaged rock) fall back to a full array.
 
 use super::Tile;
 use bevy::prelude::*;
 
 pub const CHUNK_SIZE: usize = 16;
 
+/// Number of tiles in a chunk
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Maximum palette entries addressable by a byte index
+const MAX_PALETTE: usize = 256;
+
+/// How a chunk's tiles are stored
+#[derive(Clone)]
+enum ChunkStorage {
+    /// Every tile is the same
+    Uniform(Tile),
+    /// Up to 256 distinct tiles, one palette index per position
+    Palette {
+        palette: Vec<Tile>,
+        indices: Box<[u8; CHUNK_VOLUME]>,
+    },
+    /// Too many distinct tiles for a palette
+    Full(Box<[Tile; CHUNK_VOLUME]>),
+}
+
 /// A 16x16x16 chunk of tiles
 #[derive(Clone)]
 pub struct Chunk {
-    tiles: Box<[[[Tile; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE]>,
+    storage: ChunkStorage,
     /// Dirty flag for flow field recalculation
     pub flow_field_dirty: bool,
 }
@@ -19,44 +44,246 @@ impl Default for Chunk {
     }
 }
 
+/// Flat index of a local position (x-major, matching `iter_tiles`)
+fn index(pos: UVec3) -> usize {
+    (pos.x as usize * CHUNK_SIZE + pos.y as usize) * CHUNK_SIZE + pos.z as usize
+}
+
+/// Local position of a flat index
+fn position(index: usize) -> UVec3 {
+    UVec3::new(
+        (index / (CHUNK_SIZE * CHUNK_SIZE)) as u32,
+        (index / CHUNK_SIZE % CHUNK_SIZE) as u32,
+        (index % CHUNK_SIZE) as u32,
+    )
+}
+
 impl Chunk {
     pub fn new() -> Self {
-        Self {
-            tiles: Box::new([[[Tile::Air; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE]),
-            flow_field_dirty: true,
-        }
+        Self::filled(Tile::Air)
     }
 
     /// Create a chunk filled with a specific tile
     pub fn filled(tile: Tile) -> Self {
         Self {
-            tiles: Box::new([[[tile; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE]),
+            storage: ChunkStorage::Uniform(tile),
             flow_field_dirty: true,
         }
     }
 
     /// Get a tile at local chunk position
     pub fn get_tile(&self, pos: UVec3) -> &Tile {
-        &self.tiles[pos.x as usize][pos.y as usize][pos.z as usize]
+        self.tile_at_index(index(pos))
+    }
+
+    fn tile_at_index(&self, i: usize) -> &Tile {
+        match &self.storage {
+            ChunkStorage::Uniform(tile) => tile,
+            ChunkStorage::Palette { palette, indices } => &palette[indices[i] as usize],
+            ChunkStorage::Full(tiles) => &tiles[i],
+        }
     }
 
     /// Set a tile at local chunk position
     pub fn set_tile(&mut self, pos: UVec3, tile: Tile) {
-        self.tiles[pos.x as usize][pos.y as usize][pos.z as usize] = tile;
         self.flow_field_dirty = true;
+        let i = index(pos);
+
+        match &mut self.storage {
+            ChunkStorage::Uniform(current) => {
+                if *current == tile {
+                    return;
+                }
+                // Expand on first differing write
+                let mut indices = Box::new([0u8; CHUNK_VOLUME]);
+                indices[i] = 1;
+                self.storage = ChunkStorage::Palette {
+                    palette: vec![*current, tile],
+                    indices,
+                };
+            }
+            ChunkStorage::Palette { palette, indices } => {
+                if let Some(entry) = palette.iter().position(|t| *t == tile) {
+                    indices[i] = entry as u8;
+                } else if palette.len() < MAX_PALETTE {
+                    indices[i] = palette.len() as u8;
+                    palette.push(tile);
+                } else {
+                    // Palette full - drop unused entries, or give up on it
+                    self.compact();
+                    if matches!(&self.storage, ChunkStorage::Palette { palette, .. } if palette.len() >= MAX_PALETTE)
+                    {
+                        self.expand_full();
+                    }
+                    self.set_tile(pos, tile);
+                }
+            }
+            ChunkStorage::Full(tiles) => tiles[i] = tile,
+        }
+    }
+
+    /// Switch to full per-tile storage
+    fn expand_full(&mut self) {
+        let tiles: Vec<Tile> = (0..CHUNK_VOLUME).map(|i| *self.tile_at_index(i)).collect();
+        let tiles: Box<[Tile; CHUNK_VOLUME]> = tiles
+            .into_boxed_slice()
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("chunk has CHUNK_VOLUME tiles"));
+        self.storage = ChunkStorage::Full(tiles);
+    }
+
+    /// Re-pack storage as tightly as possible (uniform if it can be).
+    /// Doesn't count as a tile change.
+    pub fn compact(&mut self) {
+        let mut palette: Vec<Tile> = Vec::new();
+        let mut indices = Box::new([0u8; CHUNK_VOLUME]);
+
+        for (i, slot) in indices.iter_mut().enumerate() {
+            let tile = *self.tile_at_index(i);
+            let entry = match palette.iter().position(|t| *t == tile) {
+                Some(entry) => entry,
+                None if palette.len() < MAX_PALETTE => {
+                    palette.push(tile);
+                    palette.len() - 1
+                }
+                None => {
+                    // Too varied for a palette
+                    if !matches!(self.storage, ChunkStorage::Full(_)) {
+                        self.expand_full();
+                    }
+                    return;
+                }
+            };
+            *slot = entry as u8;
+        }
+
+        self.storage = if palette.len() == 1 {
+            ChunkStorage::Uniform(palette[0])
+        } else {
+            ChunkStorage::Palette { palette, indices }
+        };
+    }
+
+    /// The single tile filling this chunk, if it is uniform
+    pub fn uniform_tile(&self) -> Option<&Tile> {
+        match &self.storage {
+            ChunkStorage::Uniform(tile) => Some(tile),
+            _ => None,
+        }
+    }
+
+    /// Approximate heap bytes used by tile storage
+    pub fn storage_bytes(&self) -> usize {
+        match &self.storage {
+            ChunkStorage::Uniform(_) => 0,
+            ChunkStorage::Palette { palette, .. } => {
+                palette.len() * std::mem::size_of::<Tile>() + CHUNK_VOLUME
+            }
+            ChunkStorage::Full(_) => CHUNK_VOLUME * std::mem::size_of::<Tile>(),
+        }
+    }
+
+    /// Run-length encode the tiles in `iter_tiles` order
+    pub fn runs(&self) -> Vec<(u32, Tile)> {
+        if let ChunkStorage::Uniform(tile) = &self.storage {
+            return vec![(CHUNK_VOLUME as u32, *tile)];
+        }
+
+        let mut runs: Vec<(u32, Tile)> = Vec::new();
+        for i in 0..CHUNK_VOLUME {
+            let tile = *self.tile_at_index(i);
+            match runs.last_mut() {
+                Some((count, last)) if *last == tile => *count += 1,
+                _ => runs.push((1, tile)),
+            }
+        }
+        runs
+    }
+
+    /// Rebuild a chunk from runs. Returns None if they don't cover the chunk.
+    pub fn from_runs(runs: &[(u32, Tile)]) -> Option<Self> {
+        let total: u64 = runs.iter().map(|(count, _)| *count as u64).sum();
+        if total != CHUNK_VOLUME as u64 {
+            return None;
+        }
+
+        let mut chunk = Self::new();
+        let mut i = 0;
+        for (count, tile) in runs {
+            for _ in 0..*count {
+                chunk.set_tile(position(i), *tile);
+                i += 1;
+            }
+        }
+        chunk.compact();
+        chunk.flow_field_dirty = true;
+        Some(chunk)
     }
 
     /// Iterate over all tiles with their local positions
     pub fn iter_tiles(&self) -> impl Iterator<Item = (UVec3, &Tile)> {
-        (0..CHUNK_SIZE).flat_map(move |x| {
-            (0..CHUNK_SIZE).flat_map(move |y| {
-                (0..CHUNK_SIZE).map(move |z| {
-                    (
-                        UVec3::new(x as u32, y as u32, z as u32),
-                        &self.tiles[x][y][z],
-                    )
-                })
-            })
-        })
+        (0..CHUNK_VOLUME).map(move |i| (position(i), self.tile_at_index(i)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles(chunk: &Chunk) -> Vec<Tile> {
+        chunk.iter_tiles().map(|(_, tile)| *tile).collect()
+    }
+
+    /// Stone damaged to one of `kinds` different HP values, by position
+    fn damaged_stone(i: usize, kinds: usize) -> Tile {
+        Tile::Stone {
+            hp: (i % kinds) as u16 + 1,
+            max_hp: 400,
+        }
+    }
+
+    /// Runs decode back to the same tiles, and compacting changes nothing
+    fn assert_round_trip(chunk: &Chunk) {
+        let expected = tiles(chunk);
+        let decoded = Chunk::from_runs(&chunk.runs()).expect("runs cover the chunk");
+        assert_eq!(tiles(&decoded), expected);
+
+        let mut compacted = chunk.clone();
+        compacted.compact();
+        assert_eq!(tiles(&compacted), expected);
+    }
+
+    #[test]
+    fn storage_switches_without_changing_tiles() {
+        let mut chunk = Chunk::filled(Tile::Rubble);
+        assert!(matches!(chunk.storage, ChunkStorage::Uniform(_)));
+        assert_round_trip(&chunk);
+
+        // Palette overflows into full storage one tile at a time
+        for i in 0..CHUNK_VOLUME {
+            chunk.set_tile(position(i), damaged_stone(i, 300));
+        }
+        assert!(matches!(chunk.storage, ChunkStorage::Full(_)));
+        assert_round_trip(&chunk);
+
+        // Repairs bring it back under the palette limit
+        for i in 0..CHUNK_VOLUME {
+            chunk.set_tile(position(i), damaged_stone(i, 3));
+        }
+        let before = tiles(&chunk);
+        chunk.compact();
+        let ChunkStorage::Palette { palette, .. } = &chunk.storage else {
+            panic!("three distinct tiles fit a palette");
+        };
+        assert_eq!(palette.len(), 3);
+        assert_eq!(tiles(&chunk), before);
+        assert_round_trip(&chunk);
+
+        for i in 0..CHUNK_VOLUME {
+            chunk.set_tile(position(i), Tile::Air);
+        }
+        chunk.compact();
+        assert_eq!(chunk.uniform_tile(), Some(&Tile::Air));
+        assert!(Chunk::from_runs(&[(1, Tile::Air)]).is_none());
     }
 }
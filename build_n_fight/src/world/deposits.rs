@@ -0,0 +1,79 @@
+//! Resource deposits - finite ore and timber
+//!
+//! Ore and tree tiles hold the yield listed in the tile registry. Mining
+//! draws it out a few units at a time; once a tile is mined out it turns
+//! into its `depletes_to` kind (stone for ore, air for trees). Digging a
+//! tile out grants whatever was still in it.
+
+use super::{GameWorld, Tile, TileChanges, TileKind, TileRegistry, TileYield};
+use bevy::prelude::*;
+
+/// What is left in partly mined tiles
+///
+/// Untouched tiles aren't stored; they still hold their full yield.
+#[derive(Resource, Default)]
+pub struct Deposits {
+    remaining: hashbrown::HashMap<IVec3, TileYield>,
+}
+
+impl Deposits {
+    /// Resources still in a tile
+    pub fn remaining(&self, world: &GameWorld, pos: IVec3) -> TileYield {
+        self.remaining.get(&pos).copied().unwrap_or_else(|| {
+            world
+                .get_tile(pos)
+                .map(|tile| tile.def().yields)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Take up to `units` out of a tile
+    pub fn extract(&mut self, world: &GameWorld, pos: IVec3, units: u32) -> TileYield {
+        let mut left = self.remaining(world, pos);
+        let taken = left.take(units);
+        self.remaining.insert(pos, left);
+        taken
+    }
+
+    /// Take everything out of a tile (it is being dug away)
+    pub fn take_all(&mut self, world: &GameWorld, pos: IVec3) -> TileYield {
+        let all = self.remaining(world, pos);
+        self.remaining.remove(&pos);
+        all
+    }
+
+    /// Override what a tile holds (loading, undo)
+    pub fn set(&mut self, pos: IVec3, remaining: TileYield) {
+        self.remaining.insert(pos, remaining);
+    }
+
+    /// Partly mined tiles (for saving)
+    pub fn entries(&self) -> impl Iterator<Item = (IVec3, TileYield)> + '_ {
+        self.remaining.iter().map(|(pos, left)| (*pos, *left))
+    }
+
+    pub fn clear(&mut self) {
+        self.remaining.clear();
+    }
+}
+
+/// What a deposit tile turns into once mined out
+pub fn depleted_tile(registry: &TileRegistry, tile: &Tile) -> Tile {
+    let kind = tile
+        .def()
+        .depletes_to
+        .as_deref()
+        .and_then(|name| registry.kind(name))
+        .unwrap_or(TileKind::AIR);
+    Tile::from_kind(kind)
+}
+
+/// System to forget what was left in deposit tiles that were replaced
+pub fn forget_replaced_deposits(mut deposits: ResMut<Deposits>, mut events: TileChanges) {
+    for event in events.read() {
+        if !event.old_tile.def().yields.is_empty() && event.old_tile.kind() != event.new_tile.kind()
+        {
+            deposits.remaining.remove(&event.position);
+        }
+    }
+}
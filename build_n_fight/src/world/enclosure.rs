@@ -0,0 +1,366 @@
+//! Enclosure analysis - which tiles are inside the player's base?
+//!
+//! Flood fill over open tiles at or below the surface. Anything connected
+//! to the edge of the loaded world is outside; surface tiles cut off from
+//! the edge (and whatever hangs off them) are inside. Walls block movement,
+//! and floors are walkable but seal vertically. Streaming a chunk in or out
+//! only reclassifies that chunk and the tiles bordering it.
+
+use super::{
+    chunk_position, ChunkLoadedEvent, ChunkUnloadedEvent, GameWorld, Region, Tile, TileChanges,
+    HORIZONTAL, NEIGHBORS,
+};
+use bevy::prelude::*;
+
+const VERTICAL: [IVec3; 2] = [IVec3::new(0, 0, 1), IVec3::new(0, 0, -1)];
+
+/// Inside/outside classification of open tiles
+#[derive(Resource)]
+pub struct Enclosure {
+    inside: hashbrown::HashSet<IVec3>,
+    outside: hashbrown::HashSet<IVec3>,
+    /// Whether a full recompute is needed
+    pub dirty: bool,
+}
+
+impl Default for Enclosure {
+    fn default() -> Self {
+        Self {
+            inside: hashbrown::HashSet::new(),
+            outside: hashbrown::HashSet::new(),
+            dirty: true,
+        }
+    }
+}
+
+/// Can the enclosure flood fill stand on this tile?
+fn is_open(tile: Option<&Tile>) -> bool {
+    tile.is_some_and(|t| t.is_passable() || matches!(t, Tile::Floor { .. }))
+}
+
+fn is_floor(tile: Option<&Tile>) -> bool {
+    matches!(tile, Some(Tile::Floor { .. }))
+}
+
+/// Does this tile border unloaded ground (the edge of the world)?
+fn on_edge(world: &GameWorld, pos: IVec3) -> bool {
+    HORIZONTAL
+        .iter()
+        .any(|offset| world.get_tile(pos + *offset).is_none())
+}
+
+/// Open tiles at or below the surface in a chunk
+fn open_tiles(world: &GameWorld, chunk: IVec3) -> impl Iterator<Item = IVec3> + '_ {
+    Region::chunk(chunk)
+        .positions()
+        .filter(|pos| pos.z <= world.surface_z && is_open(world.get_tile(*pos)))
+}
+
+/// Tiles outside a chunk that touch it
+fn chunk_border(chunk: IVec3) -> hashbrown::HashSet<IVec3> {
+    Region::chunk(chunk)
+        .positions()
+        .flat_map(|pos| NEIGHBORS.map(|offset| pos + offset))
+        .filter(|pos| chunk_position(*pos) != chunk)
+        .collect()
+}
+
+impl Enclosure {
+    /// Is this tile inside the base?
+    pub fn is_inside(&self, pos: IVec3) -> bool {
+        self.inside.contains(&pos)
+    }
+
+    /// Is this tile connected to the outside world?
+    pub fn is_outside(&self, pos: IVec3) -> bool {
+        self.outside.contains(&pos)
+    }
+
+    /// All tiles inside the base
+    pub fn inside_tiles(&self) -> impl Iterator<Item = &IVec3> {
+        self.inside.iter()
+    }
+
+    /// Is this a wall segment bordering the inside of the base?
+    pub fn is_perimeter(&self, world: &GameWorld, pos: IVec3) -> bool {
+        matches!(world.get_tile(pos), Some(Tile::Wall { .. }))
+            && HORIZONTAL
+                .iter()
+                .any(|offset| self.is_inside(pos + *offset))
+    }
+
+    /// All perimeter wall segments
+    pub fn perimeter(&self, world: &GameWorld) -> Vec<IVec3> {
+        let mut walls: Vec<IVec3> = self
+            .inside
+            .iter()
+            .flat_map(|pos| HORIZONTAL.iter().map(move |offset| *pos + *offset))
+            .filter(|pos| matches!(world.get_tile(*pos), Some(Tile::Wall { .. })))
+            .collect();
+        walls.sort_by_key(|p| p.to_array());
+        walls.dedup();
+        walls
+    }
+
+    /// Open neighbours reachable from a tile under enclosure rules
+    fn links(world: &GameWorld, pos: IVec3) -> Vec<IVec3> {
+        let here = world.get_tile(pos);
+        let mut links: Vec<IVec3> = HORIZONTAL
+            .iter()
+            .map(|offset| pos + *offset)
+            .filter(|next| next.z <= world.surface_z && is_open(world.get_tile(*next)))
+            .collect();
+
+        if !is_floor(here) {
+            links.extend(VERTICAL.iter().map(|offset| pos + *offset).filter(|next| {
+                let tile = world.get_tile(*next);
+                next.z <= world.surface_z && is_open(tile) && !is_floor(tile)
+            }));
+        }
+        links
+    }
+
+    /// Flood fill from `start`, calling `visit` on each new open tile.
+    /// Stops early (returning true) when `stop` matches a tile.
+    fn flood(
+        world: &GameWorld,
+        start: IVec3,
+        mut skip: impl FnMut(IVec3) -> bool,
+        mut stop: impl FnMut(IVec3) -> bool,
+    ) -> (Vec<IVec3>, bool) {
+        let mut visited = hashbrown::HashSet::new();
+        let mut queue = std::collections::VecDeque::new();
+        visited.insert(start);
+        queue.push_back(start);
+        let mut order = Vec::new();
+
+        while let Some(pos) = queue.pop_front() {
+            order.push(pos);
+            if stop(pos) {
+                return (order, true);
+            }
+            for next in Self::links(world, pos) {
+                if !skip(next) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        (order, false)
+    }
+
+    /// Recompute the whole classification
+    pub fn recompute(&mut self, world: &GameWorld) {
+        self.inside.clear();
+        self.outside.clear();
+        self.dirty = false;
+
+        let chunks: Vec<IVec3> = world.chunks.keys().copied().collect();
+        for chunk in chunks {
+            self.classify_chunk(world, chunk);
+        }
+    }
+
+    /// Classify the open tiles of a chunk that aren't classified yet.
+    /// Whatever reaches the edge or the known outside is outside; the rest
+    /// is inside if it reaches the surface.
+    fn classify_chunk(&mut self, world: &GameWorld, chunk: IVec3) {
+        let mut seen = hashbrown::HashSet::new();
+        for pos in open_tiles(world, chunk) {
+            if self.outside.contains(&pos) || self.inside.contains(&pos) || seen.contains(&pos) {
+                continue;
+            }
+            let outside = &self.outside;
+            let (component, escaped) = Self::flood(
+                world,
+                pos,
+                |_| false,
+                |p| outside.contains(&p) || on_edge(world, p),
+            );
+            if escaped {
+                self.mark_outside(world, pos);
+            } else if component.iter().any(|p| p.z == world.surface_z) {
+                self.inside.extend(component);
+            } else {
+                seen.extend(component);
+            }
+        }
+    }
+
+    /// Everything reachable from `start` is outside
+    fn mark_outside(&mut self, world: &GameWorld, start: IVec3) {
+        let outside = &self.outside;
+        let (reached, _) = Self::flood(world, start, |p| outside.contains(&p), |_| false);
+        for tile in reached {
+            self.inside.remove(&tile);
+            self.outside.insert(tile);
+        }
+    }
+
+    /// Recheck an outside tile whose way out may have been cut off
+    fn recheck_outside(&mut self, world: &GameWorld, start: IVec3) {
+        if !self.outside.contains(&start) {
+            return; // Already reclassified via another tile
+        }
+        let (component, escaped) = Self::flood(world, start, |_| false, |p| on_edge(world, p));
+        if escaped {
+            return;
+        }
+        let enclosed = component.iter().any(|p| p.z == world.surface_z);
+        for tile in component {
+            self.outside.remove(&tile);
+            if enclosed {
+                self.inside.insert(tile);
+            }
+        }
+    }
+
+    /// Classify a chunk that just streamed in. Its neighbours no longer
+    /// border the edge there, so their outside tiles are rechecked too.
+    pub fn chunk_loaded(&mut self, world: &GameWorld, chunk: IVec3) {
+        for pos in chunk_border(chunk) {
+            self.recheck_outside(world, pos);
+        }
+        self.classify_chunk(world, chunk);
+    }
+
+    /// Forget a chunk that streamed out. Its neighbours now border the
+    /// edge there, and anything that only got out through it is rechecked.
+    pub fn chunk_unloaded(&mut self, world: &GameWorld, chunk: IVec3) {
+        for pos in Region::chunk(chunk).positions() {
+            self.inside.remove(&pos);
+            self.outside.remove(&pos);
+        }
+
+        let border = chunk_border(chunk);
+        for pos in &border {
+            if pos.z <= world.surface_z
+                && !self.outside.contains(pos)
+                && is_open(world.get_tile(*pos))
+                && on_edge(world, *pos)
+            {
+                self.mark_outside(world, *pos);
+            }
+        }
+        for pos in border {
+            self.recheck_outside(world, pos);
+        }
+    }
+
+    /// Update classification after a single tile changed
+    pub fn apply_change(&mut self, world: &GameWorld, pos: IVec3, old_tile: &Tile) {
+        let was_open = is_open(Some(old_tile));
+        let now_open = is_open(world.get_tile(pos));
+        let seal_changed = is_floor(Some(old_tile)) != is_floor(world.get_tile(pos));
+        if (was_open == now_open && !seal_changed) || pos.z > world.surface_z {
+            return;
+        }
+
+        self.inside.remove(&pos);
+        self.outside.remove(&pos);
+
+        // Opened (or floor removed): merge with whatever it now touches
+        if now_open {
+            let links = Self::links(world, pos);
+            if on_edge(world, pos) || links.iter().any(|n| self.outside.contains(n)) {
+                self.mark_outside(world, pos);
+            } else if pos.z == world.surface_z || links.iter().any(|n| self.inside.contains(n)) {
+                let inside = &self.inside;
+                let (reached, _) = Self::flood(world, pos, |p| inside.contains(&p), |_| false);
+                self.inside.extend(reached);
+            }
+        }
+
+        // Closed (or floor laid): the outside may have been cut in two
+        if !now_open || seal_changed {
+            let candidates: Vec<IVec3> = HORIZONTAL
+                .iter()
+                .chain(VERTICAL.iter())
+                .map(|offset| pos + *offset)
+                .chain(std::iter::once(pos))
+                .filter(|n| self.outside.contains(n))
+                .collect();
+
+            for start in candidates {
+                self.recheck_outside(world, start);
+            }
+        }
+    }
+}
+
+/// System to keep the enclosure map in sync with tile changes and chunk
+/// streaming
+pub fn update_enclosure(
+    mut enclosure: ResMut<Enclosure>,
+    world: Res<GameWorld>,
+    mut tile_events: TileChanges,
+    mut loaded_events: EventReader<ChunkLoadedEvent>,
+    mut unloaded_events: EventReader<ChunkUnloadedEvent>,
+) {
+    if enclosure.dirty {
+        tile_events.clear();
+        loaded_events.clear();
+        unloaded_events.clear();
+        enclosure.recompute(&world);
+        return;
+    }
+
+    for event in unloaded_events.read() {
+        enclosure.chunk_unloaded(&world, event.chunk);
+    }
+    for event in loaded_events.read() {
+        // Already unloaded again
+        if world.chunks.contains_key(&event.chunk) {
+            enclosure.chunk_loaded(&world, event.chunk);
+        }
+    }
+    for event in tile_events.read() {
+        enclosure.apply_change(&world, event.position, &event.old_tile);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::TileKind;
+
+    /// Open surface with a closed ring of wall from (5, 5) to (9, 9)
+    fn walled_yard() -> GameWorld {
+        let mut world = GameWorld::new();
+        world.chunks.insert(IVec3::ZERO, Default::default());
+        let wall = Tile::from_kind(TileKind::STONE_WALL);
+        for pos in Region::cuboid(IVec3::new(5, 5, 0), IVec3::new(9, 9, 0)).positions() {
+            if pos.x == 5 || pos.x == 9 || pos.y == 5 || pos.y == 9 {
+                world.set_tile(pos, wall);
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn yard_opens_to_the_outside_when_its_wall_breaks() {
+        let mut world = walled_yard();
+        let mut enclosure = Enclosure::default();
+        enclosure.recompute(&world);
+        let (yard, field) = (IVec3::new(7, 7, 0), IVec3::new(1, 1, 0));
+        assert!(enclosure.is_inside(yard) && !enclosure.is_outside(yard));
+        assert!(enclosure.is_outside(field) && !enclosure.is_inside(field));
+        assert!(enclosure.is_perimeter(&world, IVec3::new(5, 7, 0)));
+
+        let breach = IVec3::new(5, 7, 0);
+        let old_tile = *world.get_tile(breach).unwrap();
+        world.set_tile(breach, Tile::Air);
+        enclosure.apply_change(&world, breach, &old_tile);
+        assert!(enclosure.is_outside(yard) && !enclosure.is_inside(yard));
+        assert!(enclosure.is_outside(breach));
+
+        // Patching the wall encloses the yard again, just like a recompute
+        world.set_tile(breach, old_tile);
+        enclosure.apply_change(&world, breach, &Tile::Air);
+        let mut expected = Enclosure::default();
+        expected.recompute(&world);
+        assert!(enclosure.is_inside(yard));
+        assert_eq!(enclosure.inside, expected.inside);
+        assert_eq!(enclosure.outside, expected.outside);
+    }
+}
@@ -0,0 +1,275 @@
+//! Environment - heat, gas pockets and unstable ground at depth
+//!
+//! Conditions are derived from the seed and tile position with the same
+//! hash noise as world generation, so nothing needs storing except which
+//! gas pockets have already gone off. Heat rises with depth, draining away
+//! team supplies and slowing ants that can't take it. Unstable ground
+//! generates with less HP. Gas pockets explode when a flamer lights them.
+
+use super::{
+    value_noise, ChangeCause, ChunkChangedEvent, Crushable, GameWorld, Region, Tile, WorldGenConfig,
+    NEIGHBORS,
+};
+use crate::combat::{DamageEvent, DamageTarget, Weapon};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Heat starts rising this far below the surface, peaking at the floor
+const HEAT_MIN_DEPTH: i32 = 6;
+
+/// Gas pockets only form this deep
+const GAS_MIN_DEPTH: i32 = 10;
+/// Noise above this holds gas
+const GAS_THRESHOLD: f32 = 0.8;
+
+/// Unstable ground only forms this deep
+const UNSTABLE_MIN_DEPTH: i32 = 5;
+/// Noise above this is unstable
+const UNSTABLE_THRESHOLD: f32 = 0.6;
+/// Instability is rounded up to this many steps, so weakened tiles stay
+/// few enough kinds for chunk palettes
+const INSTABILITY_STEPS: f32 = 3.0;
+/// Fraction of HP lost by fully unstable ground
+const MAX_HP_LOSS: f32 = 0.5;
+
+const SALT_GAS: u64 = 6;
+const SALT_UNSTABLE: u64 = 7;
+
+/// Reach and damage of an exploding gas pocket
+pub const GAS_BLAST_RADIUS: i32 = 2;
+pub const GAS_BLAST_DAMAGE: f32 = 60.0;
+/// Largest pocket vented by a single spark
+const MAX_POCKET: usize = 64;
+
+/// Extra supply drain per unit of heat (1.0 doubles consumption)
+pub const HEAT_SUPPLY_DRAIN: f32 = 1.5;
+
+/// Conditions at a tile
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Conditions {
+    /// 0 near the surface up to 1 at the bottom of the world
+    pub heat: f32,
+    /// Trapped gas that explodes when lit
+    pub gas: bool,
+    /// 0 for solid ground up to 1 for crumbling ground
+    pub instability: f32,
+}
+
+impl WorldGenConfig {
+    /// Natural conditions at a world position
+    pub fn conditions_at(&self, pos: IVec3, surface_z: i32) -> Conditions {
+        let depth = surface_z - pos.z;
+        let heat = ((depth - HEAT_MIN_DEPTH) as f32 / (self.depth - HEAT_MIN_DEPTH).max(1) as f32)
+            .clamp(0.0, 1.0);
+
+        let p = pos.as_vec3();
+        let gas =
+            depth >= GAS_MIN_DEPTH && value_noise(self.seed, SALT_GAS, p / 3.0) > GAS_THRESHOLD;
+
+        let instability = if depth >= UNSTABLE_MIN_DEPTH {
+            let noise = value_noise(self.seed, SALT_UNSTABLE, p / 5.0);
+            let raw = ((noise - UNSTABLE_THRESHOLD) / (1.0 - UNSTABLE_THRESHOLD)).clamp(0.0, 1.0);
+            (raw * INSTABILITY_STEPS).ceil() / INSTABILITY_STEPS
+        } else {
+            0.0
+        };
+
+        Conditions {
+            heat,
+            gas,
+            instability,
+        }
+    }
+}
+
+/// A natural tile as generated on unstable ground
+pub(super) fn weaken(tile: Tile, instability: f32) -> Tile {
+    if instability <= 0.0 {
+        return tile;
+    }
+    let scale = |hp: u16| ((hp as f32 * (1.0 - MAX_HP_LOSS * instability)).round() as u16).max(1);
+    match tile {
+        Tile::Dirt { hp, max_hp } => Tile::Dirt {
+            hp: scale(hp),
+            max_hp: scale(max_hp),
+        },
+        Tile::Stone { hp, max_hp } => Tile::Stone {
+            hp: scale(hp),
+            max_hp: scale(max_hp),
+        },
+        Tile::Ore { hp, max_hp, ore } => Tile::Ore {
+            hp: scale(hp),
+            max_hp: scale(max_hp),
+            ore,
+        },
+        other => other,
+    }
+}
+
+/// Environment state on top of the generated conditions
+#[derive(Resource, Default)]
+pub struct EnvironmentField {
+    /// Gas pockets that have already exploded
+    vented: hashbrown::HashSet<IVec3>,
+}
+
+impl EnvironmentField {
+    /// Current conditions at a tile
+    pub fn conditions(&self, config: &WorldGenConfig, world: &GameWorld, pos: IVec3) -> Conditions {
+        let mut conditions = config.conditions_at(pos, world.surface_z);
+        conditions.gas &= !self.vented.contains(&pos);
+        conditions
+    }
+
+    /// Every vented gas tile (for saving)
+    pub fn vented(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.vented.iter().copied()
+    }
+
+    /// Mark a gas tile as already exploded
+    pub fn vent(&mut self, pos: IVec3) {
+        self.vented.insert(pos);
+    }
+
+    pub fn clear(&mut self) {
+        self.vented.clear();
+    }
+
+    /// Vent the gas pocket containing `start`, returning its tiles
+    fn vent_pocket(
+        &mut self,
+        config: &WorldGenConfig,
+        world: &GameWorld,
+        start: IVec3,
+    ) -> Vec<IVec3> {
+        let mut pocket = Vec::new();
+        let mut frontier = VecDeque::from([start]);
+        while let Some(pos) = frontier.pop_front() {
+            if pocket.len() >= MAX_POCKET || !self.conditions(config, world, pos).gas {
+                continue;
+            }
+            self.vented.insert(pos);
+            pocket.push(pos);
+            frontier.extend(NEIGHBORS.iter().map(|offset| pos + *offset));
+        }
+        pocket
+    }
+}
+
+/// System to explode gas pockets lit by flamers
+///
+/// The blast covers the whole pocket plus `GAS_BLAST_RADIUS`, and sets off
+/// any other pocket it reaches. Tiles take the damage as one batched edit.
+pub fn detonate_gas(
+    mut environment: ResMut<EnvironmentField>,
+    mut damage_events: ParamSet<(EventReader<DamageEvent>, EventWriter<DamageEvent>)>,
+    mut chunk_events: EventWriter<ChunkChangedEvent>,
+    weapons: Query<&Weapon>,
+    config: Res<WorldGenConfig>,
+    mut world: ResMut<GameWorld>,
+    victims: Crushable,
+) {
+    let sparks: Vec<IVec3> = damage_events
+        .p0()
+        .read()
+        .filter(|event| {
+            event
+                .source
+                .and_then(|source| weapons.get(source).ok())
+                .is_some_and(|weapon| weapon.ignites())
+        })
+        .map(|event| event.position)
+        .collect();
+
+    let mut blast: hashbrown::HashSet<IVec3> = hashbrown::HashSet::new();
+    let mut sparks = VecDeque::from(sparks);
+    while let Some(spark) = sparks.pop_front() {
+        let pocket = environment.vent_pocket(&config, &world, spark);
+        if pocket.is_empty() {
+            continue;
+        }
+        info!(
+            "Gas pocket of {} tiles exploded at {:?}",
+            pocket.len(),
+            spark
+        );
+
+        for center in pocket {
+            for pos in Region::sphere(center, GAS_BLAST_RADIUS).positions() {
+                if blast.insert(pos) && environment.conditions(&config, &world, pos).gas {
+                    sparks.push_back(pos);
+                }
+            }
+        }
+    }
+    if blast.is_empty() {
+        return;
+    }
+
+    let mut edit = world.edit(ChangeCause::Damage, None);
+    for pos in &blast {
+        let Some(mut tile) = edit.world().get_tile(*pos).copied() else {
+            continue;
+        };
+        if tile.is_destructible() {
+            tile.damage(GAS_BLAST_DAMAGE as u16);
+            edit.set_tile(*pos, tile);
+        }
+    }
+    chunk_events.send_batch(edit.finish());
+
+    let mut writer = damage_events.p1();
+    for (entity, transform) in victims.iter() {
+        let pos = transform.translation.as_ivec3();
+        if blast.contains(&pos) {
+            writer.send(DamageEvent {
+                target: DamageTarget::Entity(entity),
+                amount: GAS_BLAST_DAMAGE,
+                source: None,
+                position: pos,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::{update_projectiles, Projectile};
+    use crate::world::TileKind;
+
+    #[test]
+    fn flamer_hit_blasts_gas_pocket() {
+        let config = WorldGenConfig::default();
+        let mut world = GameWorld::new();
+        let spark = (0..1000)
+            .map(|x| IVec3::new(x, 0, world.surface_z - GAS_MIN_DEPTH - 2))
+            .find(|pos| config.conditions_at(*pos, world.surface_z).gas)
+            .expect("no gas pocket near the origin");
+        let wall = spark + IVec3::X;
+        world.set_tile(wall, Tile::from_kind(TileKind::STONE));
+
+        let mut app = App::new();
+        app.add_event::<DamageEvent>()
+            .add_event::<ChunkChangedEvent>()
+            .init_resource::<Time>()
+            .init_resource::<EnvironmentField>()
+            .insert_resource(config)
+            .insert_resource(world)
+            .add_systems(Update, (update_projectiles, detonate_gas).chain());
+
+        let flamer = app.world_mut().spawn(Weapon::flamer()).id();
+        app.world_mut().spawn((
+            Projectile::new(10.0, 30.0, spark, flamer),
+            Transform::from_translation(spark.as_vec3()),
+        ));
+        app.update();
+
+        let app_world = app.world();
+        let environment = app_world.resource::<EnvironmentField>();
+        assert!(environment.vented().any(|pos| pos == spark));
+        let world = app_world.resource::<GameWorld>();
+        let hp = world.get_tile(wall).and_then(|tile| tile.hp());
+        assert!(hp < Tile::from_kind(TileKind::STONE).hp());
+    }
+}
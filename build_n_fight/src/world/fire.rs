@@ -0,0 +1,248 @@
+//! Fire - flammable tiles ignite, spread and burn out into Rubble
+//!
+//! Flamer hits set flammable tiles (wood walls and floors, ant structures)
+//! and whoever was hit alight. Burning tiles damage themselves and anything
+//! standing in them, spread to flammable neighbours, and add a heavy
+//! traversal cost so ants route around the flames. Water puts fires out.
+
+use super::{GameWorld, SecondTick, WaterField, NEIGHBORS};
+use crate::ai::Ant;
+use crate::combat::{DamageEvent, DamageTarget, Health, Weapon};
+use crate::flow::{CostPenalty, TraversalField};
+use bevy::prelude::*;
+use rand::Rng;
+
+/// Damage a burning tile takes per second
+pub const TILE_BURN_DAMAGE: f32 = 8.0;
+/// Damage per second to anything standing in or carrying fire
+pub const ENTITY_BURN_DAMAGE: f32 = 4.0;
+/// Seconds a set-alight entity keeps burning
+pub const ENTITY_BURN_TIME: f32 = 4.0;
+/// Extra traversal cost of a burning tile
+pub const FIRE_COST: u32 = 500;
+
+/// Burning tiles
+#[derive(Resource)]
+pub struct FireField {
+    burning: hashbrown::HashSet<IVec3>,
+    /// Seconds between fire steps
+    pub interval: f32,
+    timer: f32,
+}
+
+impl Default for FireField {
+    fn default() -> Self {
+        Self {
+            burning: hashbrown::HashSet::new(),
+            interval: 0.5,
+            timer: 0.0,
+        }
+    }
+}
+
+impl FireField {
+    pub fn is_burning(&self, pos: IVec3) -> bool {
+        self.burning.contains(&pos)
+    }
+
+    /// Every burning tile (for saving and rendering)
+    pub fn burning(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.burning.iter().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.burning.clear();
+    }
+
+    /// Set a tile alight if it can burn; returns whether it caught
+    pub fn ignite(&mut self, world: &GameWorld, pos: IVec3) -> bool {
+        let flammable = world
+            .get_tile(pos)
+            .is_some_and(|t| t.def().flammability > 0.0);
+        flammable && self.burning.insert(pos)
+    }
+}
+
+/// An entity on fire
+#[derive(Component)]
+pub struct Burning {
+    pub remaining: f32,
+}
+
+/// System to light fires where flamers hit
+pub fn ignite_from_flamers(
+    mut commands: Commands,
+    mut fire: ResMut<FireField>,
+    mut traversal: ResMut<TraversalField>,
+    mut damage_events: EventReader<DamageEvent>,
+    weapons: Query<&Weapon>,
+    world: Res<GameWorld>,
+) {
+    for event in damage_events.read() {
+        let ignites = event
+            .source
+            .and_then(|source| weapons.get(source).ok())
+            .is_some_and(|weapon| weapon.ignites());
+        if !ignites {
+            continue;
+        }
+
+        match &event.target {
+            DamageTarget::Tile(pos) => {
+                if fire.ignite(&world, *pos) {
+                    traversal.set_penalty(CostPenalty::Fire, *pos, FIRE_COST);
+                }
+            }
+            DamageTarget::Entity(entity) => {
+                if let Some(mut entity) = commands.get_entity(*entity) {
+                    entity.try_insert(Burning {
+                        remaining: ENTITY_BURN_TIME,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// System to burn, spread and put out fires
+pub fn spread_fire(
+    mut fire: ResMut<FireField>,
+    mut traversal: ResMut<TraversalField>,
+    mut damage_events: EventWriter<DamageEvent>,
+    world: Res<GameWorld>,
+    water: Res<WaterField>,
+    time: Res<Time>,
+) {
+    fire.timer += time.delta_secs();
+    if fire.timer < fire.interval {
+        return;
+    }
+    let dt = fire.timer;
+    fire.timer = 0.0;
+
+    let mut rng = rand::thread_rng();
+    let mut out = Vec::new();
+    let mut caught = Vec::new();
+    for pos in fire.burning.iter().copied() {
+        // Burned out, or doused
+        let flammable = world
+            .get_tile(pos)
+            .is_some_and(|t| t.def().flammability > 0.0);
+        if !flammable || water.level(pos) > 0 {
+            out.push(pos);
+            continue;
+        }
+
+        damage_events.send(DamageEvent {
+            target: DamageTarget::Tile(pos),
+            amount: TILE_BURN_DAMAGE * dt,
+            source: None,
+            position: pos,
+        });
+
+        for offset in NEIGHBORS {
+            let next = pos + offset;
+            let Some(tile) = world.get_tile(next) else {
+                continue;
+            };
+            let chance = tile.def().flammability * dt;
+            if chance > 0.0 && water.level(next) == 0 && rng.gen::<f32>() < chance {
+                caught.push(next);
+            }
+        }
+    }
+
+    for pos in out {
+        fire.burning.remove(&pos);
+        traversal.set_penalty(CostPenalty::Fire, pos, 0);
+    }
+    for pos in caught {
+        if fire.ignite(&world, pos) {
+            traversal.set_penalty(CostPenalty::Fire, pos, FIRE_COST);
+        }
+    }
+}
+
+/// Units and ants that aren't alight yet
+type Flammable<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Transform),
+    (Or<(With<Health>, With<Ant>)>, Without<Burning>),
+>;
+
+/// System to set alight anything standing in the flames
+pub fn catch_fire(mut commands: Commands, fire: Res<FireField>, standing: Flammable) {
+    for (entity, transform) in standing.iter() {
+        if fire.is_burning(transform.translation.as_ivec3()) {
+            commands.entity(entity).try_insert(Burning {
+                remaining: ENTITY_BURN_TIME,
+            });
+        }
+    }
+}
+
+/// System to hurt burning entities until they burn out
+pub fn burn_entities(
+    mut commands: Commands,
+    mut burning: Query<(Entity, &mut Burning, &Transform)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    water: Res<WaterField>,
+    tick: Res<SecondTick>,
+) {
+    if !tick.fired() {
+        return;
+    }
+    let dt = tick.elapsed();
+
+    for (entity, mut fire, transform) in burning.iter_mut() {
+        let pos = transform.translation.as_ivec3();
+        fire.remaining -= dt;
+        if fire.remaining <= 0.0 || water.level(pos) > 0 {
+            commands.entity(entity).remove::<Burning>();
+            continue;
+        }
+        damage_events.send(DamageEvent {
+            target: DamageTarget::Entity(entity),
+            amount: ENTITY_BURN_DAMAGE * dt,
+            source: None,
+            position: pos,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::{update_projectiles, Projectile};
+    use crate::world::{Tile, TileKind};
+
+    #[test]
+    fn flamer_hit_sets_wood_burning() {
+        let mut app = App::new();
+        app.add_event::<DamageEvent>()
+            .init_resource::<Time>()
+            .init_resource::<GameWorld>()
+            .init_resource::<FireField>()
+            .init_resource::<TraversalField>()
+            .add_systems(Update, (update_projectiles, ignite_from_flamers).chain());
+
+        let wood = IVec3::new(3, 0, 0);
+        let stone = IVec3::new(4, 0, 0);
+        {
+            let mut world = app.world_mut().resource_mut::<GameWorld>();
+            world.set_tile(wood, Tile::from_kind(TileKind::WOOD_WALL));
+            world.set_tile(stone, Tile::from_kind(TileKind::STONE_WALL));
+        }
+        let flamer = app.world_mut().spawn(Weapon::flamer()).id();
+        app.world_mut().spawn((
+            Projectile::new(10.0, 30.0, wood, flamer).with_aoe(2.0),
+            Transform::from_translation(wood.as_vec3()),
+        ));
+        app.update();
+
+        let fire = app.world().resource::<FireField>();
+        assert!(fire.is_burning(wood));
+        assert!(!fire.is_burning(stone));
+    }
+}
@@ -0,0 +1,362 @@
+//! Groundwater - water seeping through dirt and flooding tunnels
+//!
+//! Water is tracked per tile as a level from 0 to `MAX_WATER`. Solid tiles
+//! that let water seep (dirt) are saturated at or below the water table and
+//! feed any opening dug into them. Water falls first, then spreads sideways;
+//! it never climbs, so a flood stays at or below where it started.
+//! Passable tiles flow freely, solid ones only as fast as their `seep`.
+
+use super::{ChunkLoadedEvent, GameWorld, SecondTick, Tile, TileChanges, CHUNK_SIZE, NEIGHBORS};
+use crate::ai::{Ant, TunnelNetwork};
+use crate::combat::{DamageEvent, DamageTarget};
+use crate::flow::{CostPenalty, TraversalField};
+use bevy::prelude::*;
+
+/// A tile completely full of water
+pub const MAX_WATER: u8 = 8;
+/// Water at or above this level drowns ants and floods tunnels
+pub const DEEP_WATER: u8 = 5;
+
+/// Extra traversal cost of wading through water
+pub const SHALLOW_WATER_COST: u32 = 20;
+pub const DEEP_WATER_COST: u32 = 200;
+
+/// Damage dealt to an ant per second spent in deep water
+pub const DROWN_DAMAGE: f32 = 5.0;
+
+/// Per-tile water levels
+#[derive(Resource)]
+pub struct WaterField {
+    levels: hashbrown::HashMap<IVec3, u8>,
+    /// Tiles to update on the next flow step
+    active: hashbrown::HashSet<IVec3>,
+    /// Tiles whose level was set directly since the last step
+    touched: hashbrown::HashSet<IVec3>,
+    /// Tiles whose level changed this frame (empty between steps)
+    changed: hashbrown::HashSet<IVec3>,
+    /// Levels below the surface at which seeping ground is saturated
+    pub water_table_depth: i32,
+    /// Tiles that always hold full water (springs, burst pipes)
+    pub springs: hashbrown::HashSet<IVec3>,
+    /// Seconds between flow steps
+    pub interval: f32,
+    timer: f32,
+}
+
+impl Default for WaterField {
+    fn default() -> Self {
+        Self {
+            levels: hashbrown::HashMap::new(),
+            active: hashbrown::HashSet::new(),
+            touched: hashbrown::HashSet::new(),
+            changed: hashbrown::HashSet::new(),
+            // Inside the dirt band, so only the deepest dirt is saturated
+            water_table_depth: 4,
+            springs: hashbrown::HashSet::new(),
+            interval: 0.25,
+            timer: 0.0,
+        }
+    }
+}
+
+/// Water a tile can pass on per step (0 = watertight)
+fn flow_rate(tile: &Tile) -> u8 {
+    if tile.is_passable() {
+        MAX_WATER
+    } else {
+        tile.def().seep
+    }
+}
+
+impl WaterField {
+    /// Water standing in a tile
+    pub fn level(&self, pos: IVec3) -> u8 {
+        *self.levels.get(&pos).unwrap_or(&0)
+    }
+
+    /// Set a tile's water level and wake it up
+    pub fn set_level(&mut self, pos: IVec3, level: u8) {
+        if level == 0 {
+            self.levels.remove(&pos);
+        } else {
+            self.levels.insert(pos, level.min(MAX_WATER));
+        }
+        self.active.insert(pos);
+        self.touched.insert(pos);
+    }
+
+    /// Speed multiplier for something wading through a tile
+    pub fn movement_factor(&self, pos: IVec3) -> f32 {
+        1.0 - 0.6 * self.level(pos) as f32 / MAX_WATER as f32
+    }
+
+    /// Queue a tile and its neighbours for the next flow step
+    pub fn activate(&mut self, pos: IVec3) {
+        self.active.insert(pos);
+        self.active.extend(NEIGHBORS.iter().map(|offset| pos + *offset));
+    }
+
+    /// Tiles whose level changed this frame
+    pub fn changed(&self) -> impl Iterator<Item = &IVec3> {
+        self.changed.iter()
+    }
+
+    /// Every tile holding water (for saving)
+    pub fn entries(&self) -> impl Iterator<Item = (IVec3, u8)> + '_ {
+        self.levels.iter().map(|(pos, level)| (*pos, *level))
+    }
+
+    pub fn clear(&mut self) {
+        self.levels.clear();
+        self.active.clear();
+        self.touched.clear();
+        self.changed.clear();
+    }
+
+    /// Does this tile supply endless water?
+    fn is_source(&self, world: &GameWorld, pos: IVec3, tile: &Tile) -> bool {
+        self.springs.contains(&pos)
+            || (!tile.is_passable()
+                && tile.def().seep > 0
+                && pos.z <= world.surface_z - self.water_table_depth)
+    }
+
+    /// Wake the sources in a freshly loaded chunk that have somewhere to
+    /// flow, so groundwater starts seeping into caves and old tunnels
+    fn activate_sources(&mut self, world: &GameWorld, chunk_pos: IVec3) {
+        let Some(chunk) = world.chunks.get(&chunk_pos) else {
+            return;
+        };
+        let origin = chunk_pos * CHUNK_SIZE as i32;
+        let sources: Vec<IVec3> = chunk
+            .iter_tiles()
+            .map(|(local, tile)| (origin + local.as_ivec3(), tile))
+            .filter(|(pos, tile)| self.is_source(world, *pos, tile))
+            .filter(|(pos, _)| {
+                NEIGHBORS
+                    .iter()
+                    .any(|offset| world.get_tile(*pos + *offset).is_some_and(|t| t.is_passable()))
+            })
+            .map(|(pos, _)| pos)
+            .collect();
+        self.active.extend(sources);
+    }
+
+    /// How much water `to` can take and how fast it accepts it
+    fn room(&self, world: &GameWorld, to: IVec3) -> Option<(u8, u8)> {
+        let tile = world.get_tile(to)?;
+        let rate = flow_rate(tile);
+        if rate == 0 || self.is_source(world, to, tile) {
+            return None;
+        }
+        Some((MAX_WATER - self.level(to), rate))
+    }
+
+    /// One flow step over the active tiles; returns the tiles that changed
+    fn step(&mut self, world: &GameWorld) -> hashbrown::HashSet<IVec3> {
+        let mut cells: Vec<IVec3> = self.active.drain().collect();
+        // Lowest first so water settles before what's above it moves
+        cells.sort_by_key(|p| (p.z, p.x, p.y));
+
+        let mut changed = std::mem::take(&mut self.touched);
+        for pos in cells {
+            let Some(tile) = world.get_tile(pos) else {
+                // Unloaded - drop the water rather than track it blind
+                if self.levels.remove(&pos).is_some() {
+                    changed.insert(pos);
+                }
+                continue;
+            };
+            let source = self.is_source(world, pos, tile);
+            let rate = flow_rate(tile);
+            if rate == 0 && !source {
+                // Sealed over - the water is gone
+                if self.levels.remove(&pos).is_some() {
+                    changed.insert(pos);
+                }
+                continue;
+            }
+
+            let mut level = if source { MAX_WATER } else { self.level(pos) };
+            let mut moved_any = false;
+
+            // Fall first
+            let below = pos - IVec3::Z;
+            if let Some((space, below_rate)) = self.room(world, below) {
+                let moved = level.min(space).min(rate).min(below_rate);
+                if moved > 0 {
+                    *self.levels.entry(below).or_insert(0) += moved;
+                    level -= moved;
+                    changed.insert(below);
+                    moved_any = true;
+                }
+            }
+
+            // Then even out with lower neighbours
+            for offset in &NEIGHBORS[..4] {
+                let next = pos + *offset;
+                let Some((_, next_rate)) = self.room(world, next) else {
+                    continue;
+                };
+                let next_level = self.level(next);
+                if level < next_level + 2 {
+                    continue;
+                }
+                let moved = ((level - next_level) / 2).min(rate).min(next_rate);
+                *self.levels.entry(next).or_insert(0) += moved;
+                level -= moved;
+                changed.insert(next);
+                moved_any = true;
+            }
+
+            if moved_any && !source {
+                changed.insert(pos);
+                if level == 0 {
+                    self.levels.remove(&pos);
+                } else {
+                    self.levels.insert(pos, level);
+                }
+            } else if moved_any {
+                // Sources keep pushing until their surroundings fill up
+                self.active.insert(pos);
+            }
+        }
+
+        for pos in &changed {
+            self.activate(*pos);
+        }
+        changed
+    }
+}
+
+/// Traversal cost water adds to a tile
+fn water_penalty(world: &GameWorld, pos: IVec3, level: u8) -> u32 {
+    if !world.get_tile(pos).is_some_and(|t| t.is_passable()) {
+        return 0;
+    }
+    match level {
+        0 => 0,
+        l if l >= DEEP_WATER => DEEP_WATER_COST,
+        _ => SHALLOW_WATER_COST,
+    }
+}
+
+/// System to wake water up around changed tiles
+pub fn queue_water_updates(
+    mut water: ResMut<WaterField>,
+    mut events: TileChanges,
+    mut loaded_events: EventReader<ChunkLoadedEvent>,
+    world: Res<GameWorld>,
+) {
+    for event in events.read() {
+        water.activate(event.position);
+    }
+    for event in loaded_events.read() {
+        water.activate_sources(&world, event.chunk);
+    }
+}
+
+/// System to advance the water simulation
+pub fn simulate_water(
+    mut water: ResMut<WaterField>,
+    mut traversal: ResMut<TraversalField>,
+    world: Res<GameWorld>,
+    time: Res<Time>,
+) {
+    water.changed.clear();
+    water.timer += time.delta_secs();
+    if water.timer < water.interval {
+        return;
+    }
+    water.timer = 0.0;
+
+    let changed = water.step(&world);
+    for pos in &changed {
+        let penalty = water_penalty(&world, *pos, water.level(*pos));
+        traversal.set_penalty(CostPenalty::Water, *pos, penalty);
+    }
+    water.changed = changed;
+}
+
+/// System to drown ants standing in deep water
+pub fn drown_ants(
+    water: Res<WaterField>,
+    ants: Query<(Entity, &Transform), With<Ant>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    tick: Res<SecondTick>,
+) {
+    if !tick.fired() {
+        return;
+    }
+
+    for (entity, transform) in ants.iter() {
+        let pos = transform.translation.as_ivec3();
+        if water.level(pos) >= DEEP_WATER {
+            damage_events.send(DamageEvent {
+                target: DamageTarget::Entity(entity),
+                amount: DROWN_DAMAGE,
+                source: None,
+                position: pos,
+            });
+        }
+    }
+}
+
+/// System to flood tunnel segments running through deep water
+///
+/// Only segments crossing a tile that just became deep are checked. The
+/// segment breaks at the first flooded point and every ant queued before
+/// it drowns.
+pub fn flood_tunnels(water: Res<WaterField>, mut network: ResMut<TunnelNetwork>) {
+    let risen: hashbrown::HashSet<IVec3> = water
+        .changed()
+        .copied()
+        .filter(|pos| water.level(*pos) >= DEEP_WATER)
+        .collect();
+    if risen.is_empty() {
+        return;
+    }
+
+    for segment in network.segments.iter_mut().filter(|s| s.intact) {
+        let length = (segment.end - segment.start).abs().max_element().max(1);
+        let mut points = (0..=length).map(|step| step as f32 / length as f32);
+        if !points.clone().any(|t| risen.contains(&segment.point_at(t))) {
+            continue;
+        }
+        let flooded = points.find(|t| water.level(segment.point_at(*t)) >= DEEP_WATER);
+
+        if let Some(t) = flooded {
+            let (pos, drowned) = segment.break_at(t);
+            info!(
+                "Tunnel flooded at {:?}, {} queued ants drowned",
+                pos,
+                drowned.len()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{generate_area, WorldGenConfig};
+
+    #[test]
+    fn groundwater_seeps_into_a_fresh_world() {
+        let config = WorldGenConfig::default();
+        let mut world = GameWorld::new();
+        let spawn = config.spawn_point(world.surface_z);
+        generate_area(&config, &mut world, spawn, 2);
+
+        let mut water = WaterField::default();
+        let chunks: Vec<IVec3> = world.chunks.keys().copied().collect();
+        for chunk in chunks {
+            water.activate_sources(&world, chunk);
+        }
+        for _ in 0..20 {
+            water.step(&world);
+        }
+
+        assert!(water.entries().any(|(_, level)| level > 0));
+    }
+}
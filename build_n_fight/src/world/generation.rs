@@ -0,0 +1,360 @@
+//! Procedural world generation
+//!
+//! Everything is derived from the seed and the tile position through hash
+//! noise - no RNG state - so the same seed always yields the same world,
+//! and any chunk can be generated on its own.
+
+use super::{
+    tile_registry, weaken, AntStructureType, Chunk, GameWorld, OreType, Region, Tile, TileKind,
+    CHUNK_SIZE,
+};
+use bevy::prelude::*;
+
+/// Dirt layer thickness varies between these depths
+const MIN_DIRT_DEPTH: i32 = 2;
+const MAX_DIRT_DEPTH: i32 = 5;
+
+/// Caves only form this far below the surface
+const CAVE_MIN_DEPTH: i32 = 4;
+/// Noise above this becomes open cave
+const CAVE_THRESHOLD: f32 = 0.72;
+
+/// Ore vein thresholds (higher = rarer)
+const IRON_THRESHOLD: f32 = 0.8;
+const TUNGSTEN_THRESHOLD: f32 = 0.86;
+/// Tungsten only appears this deep
+const TUNGSTEN_MIN_DEPTH: i32 = 10;
+
+/// Chance a deep chunk holds a nest
+const NEST_CHANCE: f32 = 0.35;
+/// Nests only form this deep
+const NEST_MIN_DEPTH: i32 = 8;
+/// Radius of the chamber carved around a nest
+const NEST_CHAMBER_RADIUS: i32 = 2;
+
+/// Noise above this is woodland
+const WOODLAND_THRESHOLD: f32 = 0.6;
+/// Fraction of woodland tiles holding a tree
+const TREE_DENSITY: f32 = 0.4;
+/// No trees this close to the spawn point, so the base has room
+const TREE_CLEARING: i32 = 6;
+
+// Noise salts so each feature gets independent noise
+const SALT_DIRT: u64 = 1;
+const SALT_CAVE: u64 = 2;
+const SALT_IRON: u64 = 3;
+const SALT_TUNGSTEN: u64 = 4;
+const SALT_NEST: u64 = 5;
+// 6 and 7 are used by the environment
+const SALT_WOODLAND: u64 = 8;
+const SALT_TREE: u64 = 9;
+
+/// World generation settings
+#[derive(Resource, Debug, Clone)]
+pub struct WorldGenConfig {
+    pub seed: u64,
+    /// Horizontal size in tiles
+    pub size: IVec2,
+    /// Number of Z-levels below the surface
+    pub depth: i32,
+}
+
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0x0B17_D5F1,
+            size: IVec2::new(32, 32),
+            depth: 24,
+        }
+    }
+}
+
+/// Nest sites placed by the generator, waiting for the AI to spawn them
+#[derive(Resource, Default)]
+pub struct NestSites {
+    pub sites: Vec<IVec3>,
+}
+
+impl WorldGenConfig {
+    /// Where the player's base starts (centre of the map, on the surface)
+    pub fn spawn_point(&self, surface_z: i32) -> IVec3 {
+        IVec3::new(self.size.x / 2, self.size.y / 2, surface_z)
+    }
+
+    /// Range of chunk coordinates covered by the world
+    pub fn chunk_range(&self, surface_z: i32) -> (IVec3, IVec3) {
+        let size = IVec3::splat(CHUNK_SIZE as i32);
+        let min = IVec3::new(0, 0, surface_z - self.depth).div_euclid(size);
+        let max = IVec3::new(self.size.x - 1, self.size.y - 1, surface_z).div_euclid(size);
+        (min, max)
+    }
+
+    /// Is this chunk part of the world?
+    pub fn contains_chunk(&self, chunk: IVec3, surface_z: i32) -> bool {
+        let (min, max) = self.chunk_range(surface_z);
+        chunk.cmpge(min).all() && chunk.cmple(max).all()
+    }
+
+    /// Generate a single chunk's natural terrain (before nests are carved)
+    pub fn generate_chunk(&self, chunk_pos: IVec3, surface_z: i32) -> Chunk {
+        let size = CHUNK_SIZE as i32;
+        let origin = chunk_pos * size;
+        let mut chunk = Chunk::new();
+
+        for x in 0..size {
+            for y in 0..size {
+                for z in 0..size {
+                    let pos = origin + IVec3::new(x, y, z);
+                    let tile = self.tile_at(pos, surface_z);
+                    if tile != Tile::Air {
+                        chunk.set_tile(UVec3::new(x as u32, y as u32, z as u32), tile);
+                    }
+                }
+            }
+        }
+
+        chunk.compact();
+        chunk
+    }
+
+    /// Generate a chunk into the world and carve its nest, if it has one.
+    /// Returns the nest site.
+    pub fn generate_into(&self, world: &mut GameWorld, chunk_pos: IVec3) -> Option<IVec3> {
+        let chunk = self.generate_chunk(chunk_pos, world.surface_z);
+        world.chunks.insert(chunk_pos, chunk);
+
+        let nest = self.nest_site(chunk_pos, world.surface_z);
+        if let Some(site) = nest {
+            carve_nest(world, site);
+        }
+        nest
+    }
+
+    /// Natural tile at a world position (before nests are carved)
+    pub fn tile_at(&self, pos: IVec3, surface_z: i32) -> Tile {
+        let tile = self.base_tile_at(pos, surface_z);
+        if tile.hp().is_some() {
+            weaken(tile, self.conditions_at(pos, surface_z).instability)
+        } else {
+            tile
+        }
+    }
+
+    /// Natural tile at full HP
+    fn base_tile_at(&self, pos: IVec3, surface_z: i32) -> Tile {
+        let depth = surface_z - pos.z;
+        if depth == 0 {
+            return self.surface_tile(pos, surface_z);
+        }
+        if depth < 0 {
+            return Tile::Air;
+        }
+
+        // Beyond the footprint or below the floor of the world: solid rock
+        let inside = pos.x >= 0 && pos.y >= 0 && pos.x < self.size.x && pos.y < self.size.y;
+        if !inside || depth > self.depth {
+            return Tile::from_kind(TileKind::STONE);
+        }
+
+        let dirt_noise = value_noise(
+            self.seed,
+            SALT_DIRT,
+            pos.truncate().extend(0).as_vec3() / 8.0,
+        );
+        let dirt_depth =
+            MIN_DIRT_DEPTH + (dirt_noise * (MAX_DIRT_DEPTH - MIN_DIRT_DEPTH + 1) as f32) as i32;
+        if depth <= dirt_depth {
+            return Tile::from_kind(TileKind::DIRT);
+        }
+
+        let p = pos.as_vec3();
+        if depth >= CAVE_MIN_DEPTH && value_noise(self.seed, SALT_CAVE, p / 6.0) > CAVE_THRESHOLD {
+            return Tile::Air;
+        }
+
+        if depth >= TUNGSTEN_MIN_DEPTH
+            && value_noise(self.seed, SALT_TUNGSTEN, p / 3.0) > TUNGSTEN_THRESHOLD
+        {
+            return Tile::from_kind(TileKind::ore(OreType::Tungsten));
+        }
+        if value_noise(self.seed, SALT_IRON, p / 4.0) > IRON_THRESHOLD {
+            return Tile::from_kind(TileKind::ore(OreType::Iron));
+        }
+
+        Tile::from_kind(TileKind::STONE)
+    }
+
+    /// Open ground or a tree, on the surface level
+    fn surface_tile(&self, pos: IVec3, surface_z: i32) -> Tile {
+        let inside = pos.x >= 0 && pos.y >= 0 && pos.x < self.size.x && pos.y < self.size.y;
+        let clearing = (pos - self.spawn_point(surface_z)).abs().max_element() <= TREE_CLEARING;
+        if !inside || clearing {
+            return Tile::Air;
+        }
+
+        let woodland = value_noise(self.seed, SALT_WOODLAND, pos.as_vec3() / 6.0);
+        let tree = tile_registry().kind("tree");
+        match tree {
+            Some(tree)
+                if woodland > WOODLAND_THRESHOLD
+                    && unit(hash(self.seed, SALT_TREE, pos)) < TREE_DENSITY =>
+            {
+                Tile::from_kind(tree)
+            }
+            _ => Tile::Air,
+        }
+    }
+
+    /// Where (if anywhere) this chunk's nest sits
+    fn nest_site(&self, chunk_pos: IVec3, surface_z: i32) -> Option<IVec3> {
+        if unit(hash(self.seed, SALT_NEST, chunk_pos)) >= NEST_CHANCE {
+            return None;
+        }
+
+        // Keep the chamber inside the chunk
+        let size = CHUNK_SIZE as i32;
+        let margin = NEST_CHAMBER_RADIUS + 1;
+        let span = (size - 2 * margin) as u64;
+        let h = hash(self.seed, SALT_NEST + 1, chunk_pos);
+        let local = IVec3::new(
+            margin + (h % span) as i32,
+            margin + ((h >> 16) % span) as i32,
+            margin + ((h >> 32) % span) as i32,
+        );
+        let site = chunk_pos * size + local;
+
+        let depth = surface_z - site.z;
+        let inside = site.x < self.size.x && site.y < self.size.y && site.x >= 0 && site.y >= 0;
+        (inside && depth >= NEST_MIN_DEPTH && depth <= self.depth).then_some(site)
+    }
+}
+
+/// Hollow out a nest chamber with the nest and its storage in the middle
+fn carve_nest(world: &mut GameWorld, site: IVec3) {
+    world.fill_region(Region::sphere(site, NEST_CHAMBER_RADIUS), Tile::Air);
+
+    world.set_tile(site, Tile::from_kind(TileKind::ant_structure(AntStructureType::Nest)));
+    for offset in [IVec3::X, IVec3::NEG_X] {
+        world.set_tile(
+            site + offset,
+            Tile::from_kind(TileKind::ant_structure(AntStructureType::Storage)),
+        );
+    }
+}
+
+/// Generate every chunk of the world within `radius` chunks (horizontally)
+/// of `center`. Returns the nest sites placed.
+pub fn generate_area(
+    config: &WorldGenConfig,
+    world: &mut GameWorld,
+    center: IVec3,
+    radius: i32,
+) -> Vec<IVec3> {
+    let (min, max) = config.chunk_range(world.surface_z);
+    let center = center.div_euclid(IVec3::splat(CHUNK_SIZE as i32));
+    let mut nests = Vec::new();
+
+    for x in min.x.max(center.x - radius)..=max.x.min(center.x + radius) {
+        for y in min.y.max(center.y - radius)..=max.y.min(center.y + radius) {
+            for z in min.z..=max.z {
+                let chunk_pos = IVec3::new(x, y, z);
+                if world.chunks.contains_key(&chunk_pos) {
+                    continue;
+                }
+                nests.extend(config.generate_into(world, chunk_pos));
+            }
+        }
+    }
+
+    nests
+}
+
+/// SplitMix64-style hash of a lattice position
+fn hash(seed: u64, salt: u64, pos: IVec3) -> u64 {
+    let mut h = seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    for v in [pos.x, pos.y, pos.z] {
+        h = h
+            .wrapping_add(v as u32 as u64)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+    }
+    h
+}
+
+/// Hash mapped to [0, 1)
+fn unit(h: u64) -> f32 {
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Smoothed 3D value noise in [0, 1)
+pub(super) fn value_noise(seed: u64, salt: u64, p: Vec3) -> f32 {
+    let base = p.floor();
+    let cell = base.as_ivec3();
+    let f = p - base;
+    let t = f * f * (Vec3::splat(3.0) - 2.0 * f);
+
+    let corner = |dx: i32, dy: i32, dz: i32| unit(hash(seed, salt, cell + IVec3::new(dx, dy, dz)));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), t.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), t.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), t.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), t.x);
+    lerp(lerp(x00, x10, t.y), lerp(x01, x11, t.y), t.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every chunk of the world, generated in one pass
+    fn generate_all(config: &WorldGenConfig) -> (GameWorld, Vec<IVec3>) {
+        let mut world = GameWorld::new();
+        let radius = config.size.max_element() / CHUNK_SIZE as i32 + 1;
+        let nests = generate_area(config, &mut world, IVec3::ZERO, radius);
+        (world, nests)
+    }
+
+    fn tiles(chunk: &Chunk) -> Vec<Tile> {
+        chunk.iter_tiles().map(|(_, tile)| *tile).collect()
+    }
+
+    #[test]
+    fn same_seed_generates_the_same_world() {
+        let config = WorldGenConfig::default();
+        let (first, first_nests) = generate_all(&config);
+        let (second, second_nests) = generate_all(&config);
+        assert_eq!(first_nests, second_nests);
+        assert_eq!(first.chunks.len(), second.chunks.len());
+        for (pos, chunk) in &first.chunks {
+            assert_eq!(tiles(chunk), tiles(&second.chunks[pos]), "chunk {pos:?}");
+        }
+
+        let other = WorldGenConfig {
+            seed: config.seed + 1,
+            ..config
+        };
+        let (reseeded, _) = generate_all(&other);
+        assert!(first
+            .chunks
+            .iter()
+            .any(|(pos, chunk)| tiles(chunk) != tiles(&reseeded.chunks[pos])));
+    }
+
+    #[test]
+    fn chunk_generates_the_same_on_its_own() {
+        let config = WorldGenConfig::default();
+        let (whole, nests) = generate_all(&config);
+        for (pos, chunk) in &whole.chunks {
+            let mut alone = GameWorld::new();
+            let nest = config.generate_into(&mut alone, *pos);
+            assert_eq!(alone.chunks.len(), 1, "chunk {pos:?} spilled into its neighbours");
+            assert_eq!(tiles(chunk), tiles(&alone.chunks[pos]), "chunk {pos:?}");
+            if let Some(site) = nest {
+                assert!(nests.contains(&site), "nest {site:?} only appears alone");
+            }
+        }
+    }
+}
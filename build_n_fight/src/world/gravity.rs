@@ -0,0 +1,217 @@
+//! Rubble gravity - loose debris falls to the next solid level
+//!
+//! Rubble sitting over open space drops straight down until it lands on
+//! something, piling up on earlier rubble. Rubble that lands in a narrow
+//! shaft can optionally pack into a plug that has to be dug out again.
+
+use super::{ChangeCause, GameWorld, Tile, TileChangedEvent, TileChanges, TileKind};
+use crate::ai::Ant;
+use crate::combat::{DamageEvent, DamageTarget, Health};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Damage dealt by falling debris, plus extra per Z-level fallen
+pub const FALL_DAMAGE: f32 = 25.0;
+pub const FALL_DAMAGE_PER_LEVEL: f32 = 15.0;
+
+/// Anything falling debris can hurt: units with Health, and ants
+pub type Crushable<'w, 's> =
+    Query<'w, 's, (Entity, &'static Transform), Or<(With<Health>, With<Ant>)>>;
+
+/// Rubble waiting to be checked for falling
+#[derive(Resource)]
+pub struct RubbleGravity {
+    pending: VecDeque<IVec3>,
+    /// Does rubble landing in a one-tile shaft pack into a plug?
+    pub blocks_shafts: bool,
+}
+
+impl Default for RubbleGravity {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            blocks_shafts: true,
+        }
+    }
+}
+
+/// Can falling debris pass through this tile? Loose rubble is passable
+/// but is what debris piles up on.
+fn is_open(tile: &Tile) -> bool {
+    tile.is_passable() && *tile != Tile::Rubble
+}
+
+/// Where rubble dropped at `pos` comes to rest
+pub fn landing_position(world: &GameWorld, pos: IVec3) -> IVec3 {
+    let mut landing = pos;
+    while world.get_tile(landing - IVec3::Z).is_some_and(is_open) {
+        landing -= IVec3::Z;
+    }
+    landing
+}
+
+/// Is `pos` the bottom of a one-tile vertical shaft?
+fn is_shaft(world: &GameWorld, pos: IVec3) -> bool {
+    pos.z < world.surface_z
+        && [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y]
+            .iter()
+            .all(|offset| {
+                world
+                    .get_tile(pos + *offset)
+                    .is_none_or(|t| !t.is_passable())
+            })
+}
+
+/// Replace the tile at `pos` with air and drop rubble from it
+///
+/// Returns where the rubble came to rest.
+pub fn drop_rubble(
+    world: &mut GameWorld,
+    gravity: &RubbleGravity,
+    pos: IVec3,
+    old_tile: Tile,
+    tile_events: &mut EventWriter<TileChangedEvent>,
+) -> IVec3 {
+    world.set_tile(pos, Tile::Air);
+    let landing = landing_position(world, pos);
+    let buried = world.get_tile(landing).copied().unwrap_or(Tile::Air);
+    if landing != pos {
+        tile_events.send(TileChangedEvent {
+            position: pos,
+            old_tile,
+            new_tile: Tile::Air,
+            cause: ChangeCause::Collapse,
+            actor: None,
+        });
+    }
+
+    let rubble = if gravity.blocks_shafts && landing != pos && is_shaft(world, landing) {
+        Tile::from_kind(TileKind::RUBBLE_PLUG)
+    } else {
+        Tile::Rubble
+    };
+    world.set_tile(landing, rubble);
+    tile_events.send(TileChangedEvent {
+        position: landing,
+        old_tile: if landing == pos { old_tile } else { buried },
+        new_tile: rubble,
+        cause: ChangeCause::Collapse,
+        actor: None,
+    });
+
+    landing
+}
+
+/// Damage everything in the column debris fell through
+pub fn crush_column(
+    victims: &Crushable,
+    damage_events: &mut EventWriter<DamageEvent>,
+    from: IVec3,
+    landing: IVec3,
+) {
+    let fallen = (from.z - landing.z) as f32;
+    let amount = FALL_DAMAGE + FALL_DAMAGE_PER_LEVEL * fallen;
+    for (entity, transform) in victims.iter() {
+        let at = transform.translation.as_ivec3();
+        if at.truncate() == from.truncate() && at.z <= from.z && at.z >= landing.z {
+            damage_events.send(DamageEvent {
+                target: DamageTarget::Entity(entity),
+                amount,
+                source: None,
+                position: at,
+            });
+        }
+    }
+}
+
+/// System to queue rubble that may have lost what it rests on
+pub fn queue_rubble_checks(
+    mut gravity: ResMut<RubbleGravity>,
+    mut events: TileChanges,
+) {
+    for event in events.read() {
+        if event.new_tile == Tile::Rubble {
+            gravity.pending.push_back(event.position);
+        } else if is_open(&event.new_tile) {
+            gravity.pending.push_back(event.position + IVec3::Z);
+        }
+    }
+}
+
+/// System to drop rubble resting on open air
+pub fn settle_rubble(
+    mut gravity: ResMut<RubbleGravity>,
+    mut world: ResMut<GameWorld>,
+    mut tile_events: EventWriter<TileChangedEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    victims: Crushable,
+) {
+    while let Some(pos) = gravity.pending.pop_front() {
+        if world.get_tile(pos) != Some(&Tile::Rubble)
+            || !world.get_tile(pos - IVec3::Z).is_some_and(is_open)
+        {
+            continue;
+        }
+
+        let landing = drop_rubble(&mut world, &gravity, pos, Tile::Rubble, &mut tile_events);
+        crush_column(&victims, &mut damage_events, pos, landing);
+
+        // The rest of the pile above follows
+        gravity.pending.push_back(pos + IVec3::Z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ChunkChangedEvent, Region, CHUNK_SIZE};
+
+    /// Solid stone with a one-tile shaft from `top` down to `bottom`
+    fn shaft_world(top: IVec3, bottom: i32) -> GameWorld {
+        let mut world = GameWorld::new();
+        let chunk = IVec3::new(0, 0, -1);
+        world.set_tile(chunk * CHUNK_SIZE as i32, Tile::Air);
+        world.fill_region(Region::chunk(chunk), Tile::from_kind(TileKind::STONE));
+        for z in bottom..=top.z {
+            world.set_tile(top.with_z(z), Tile::Air);
+        }
+        world
+    }
+
+    #[test]
+    fn rubble_falls_through_open_tiles_onto_rubble() {
+        let top = IVec3::new(5, 5, -2);
+        let mut world = shaft_world(top, -10);
+        world.set_tile(top.with_z(-6), Tile::from_kind(TileKind::ANT_TUNNEL));
+        world.set_tile(top.with_z(-10), Tile::Rubble);
+        assert_eq!(landing_position(&world, top), top.with_z(-9));
+    }
+
+    #[test]
+    fn rubble_down_a_shaft_packs_into_a_plug() {
+        let top = IVec3::new(5, 5, -2);
+        let mut app = App::new();
+        app.add_event::<TileChangedEvent>()
+            .add_event::<ChunkChangedEvent>()
+            .add_event::<DamageEvent>()
+            .insert_resource(shaft_world(top, -10))
+            .init_resource::<RubbleGravity>()
+            .add_systems(Update, (queue_rubble_checks, settle_rubble).chain());
+
+        app.world_mut().resource_mut::<GameWorld>().set_tile(top, Tile::Rubble);
+        app.world_mut().send_event(TileChangedEvent {
+            position: top,
+            old_tile: Tile::Air,
+            new_tile: Tile::Rubble,
+            cause: ChangeCause::Collapse,
+            actor: None,
+        });
+        app.update();
+
+        let world = app.world().resource::<GameWorld>();
+        assert_eq!(world.get_tile(top), Some(&Tile::Air));
+        let plug = world.get_tile(top.with_z(-10)).unwrap();
+        assert_eq!(plug.kind(), TileKind::RUBBLE_PLUG);
+        assert!(!plug.is_passable() && plug.is_diggable());
+    }
+}
@@ -0,0 +1,252 @@
+//! Structural integrity - unsupported ground caves in
+//!
+//! A structural tile resting on another structural tile is grounded.
+//! Ungrounded tiles can hang off grounded neighbours on the same Z-level
+//! for up to their material's span; anything beyond that collapses.
+//! Collapsed tiles fall as Rubble to the next level down (see `gravity`),
+//! hurting whatever stands in the way.
+
+use super::{
+    crush_column, drop_rubble, tile_registry, Crushable, GameWorld, RubbleGravity, TileChangedEvent,
+    TileChanges, HORIZONTAL,
+};
+use crate::combat::DamageEvent;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Tiles waiting for a support check
+#[derive(Resource)]
+pub struct StructuralIntegrity {
+    pending: VecDeque<IVec3>,
+    queued: hashbrown::HashSet<IVec3>,
+    /// Maximum support checks per frame, so big cave-ins play out over time
+    pub budget: usize,
+}
+
+impl Default for StructuralIntegrity {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            queued: hashbrown::HashSet::new(),
+            budget: 256,
+        }
+    }
+}
+
+impl StructuralIntegrity {
+    /// Queue a tile for a support check
+    pub fn queue(&mut self, pos: IVec3) {
+        if self.queued.insert(pos) {
+            self.pending.push_back(pos);
+        }
+    }
+
+    /// Queue every tile that may have leaned on `pos`
+    pub fn queue_around(&mut self, pos: IVec3) {
+        let max_span = tile_registry().max_span();
+        for dz in 0..=1 {
+            for dx in -max_span..=max_span {
+                let reach = max_span - dx.abs();
+                for dy in -reach..=reach {
+                    self.queue(pos + IVec3::new(dx, dy, dz));
+                }
+            }
+        }
+    }
+}
+
+/// Is this tile resting on something solid?
+///
+/// Unloaded ground and the bottom of the world count as solid, so nothing
+/// collapses at the edge of what is streamed in.
+fn is_grounded(world: &GameWorld, pos: IVec3) -> bool {
+    world
+        .get_tile(pos - IVec3::Z)
+        .is_none_or(|below| below.is_structural())
+}
+
+/// Is the structural tile at `pos` held up?
+///
+/// Support flows outward from grounded tiles, dropping by one per step and
+/// capped by each tile's own span. The tile stands while its support is
+/// non-negative.
+pub fn is_supported(world: &GameWorld, pos: IVec3) -> bool {
+    let Some(tile) = world.get_tile(pos) else {
+        return true;
+    };
+    if !tile.is_structural() {
+        return true;
+    }
+    if is_grounded(world, pos) {
+        return true;
+    }
+
+    // Structural tiles connected to pos on its level, close enough to matter
+    let max_span = tile_registry().max_span();
+    let mut region: hashbrown::HashMap<IVec3, i32> = hashbrown::HashMap::new();
+    let mut frontier = VecDeque::from([(pos, 0)]);
+    region.insert(pos, -1);
+    while let Some((current, steps)) = frontier.pop_front() {
+        if steps == max_span {
+            continue;
+        }
+        for offset in HORIZONTAL {
+            let next = current + offset;
+            if region.contains_key(&next) {
+                continue;
+            }
+            if world.get_tile(next).is_some_and(|t| t.is_structural()) {
+                region.insert(next, -1);
+                frontier.push_back((next, steps + 1));
+            }
+        }
+    }
+
+    // Propagate support from grounded tiles, strongest first
+    let mut sources: Vec<(i32, IVec3)> = region
+        .keys()
+        .filter(|p| is_grounded(world, **p))
+        .map(|p| (world.get_tile(*p).map_or(0, |t| t.support_span()), *p))
+        .collect();
+    sources.sort_by_key(|(support, _)| -support);
+    let mut queue: VecDeque<(i32, IVec3)> = sources.into();
+    while let Some((support, current)) = queue.pop_front() {
+        let best = region.get_mut(&current).unwrap();
+        if support <= *best {
+            continue;
+        }
+        *best = support;
+        if current == pos {
+            return true;
+        }
+        for offset in HORIZONTAL {
+            let next = current + offset;
+            let Some(existing) = region.get(&next) else {
+                continue;
+            };
+            let span = world.get_tile(next).map_or(0, |t| t.support_span());
+            let carried = (support - 1).min(span);
+            if carried > *existing {
+                queue.push_back((carried, next));
+            }
+        }
+    }
+
+    region.get(&pos).is_some_and(|support| *support >= 0)
+}
+
+/// System to queue support checks around removed structural tiles
+pub fn queue_support_checks(
+    mut integrity: ResMut<StructuralIntegrity>,
+    mut events: TileChanges,
+) {
+    for event in events.read() {
+        if event.old_tile.is_structural() && !event.new_tile.is_structural() {
+            integrity.queue_around(event.position);
+        }
+    }
+}
+
+/// System to collapse unsupported tiles into falling Rubble
+///
+/// Each collapse is itself a removed structural tile, so cave-ins cascade
+/// over the following frames.
+pub fn collapse_unsupported(
+    mut integrity: ResMut<StructuralIntegrity>,
+    mut world: ResMut<GameWorld>,
+    gravity: Res<RubbleGravity>,
+    mut tile_events: EventWriter<TileChangedEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    victims: Crushable,
+) {
+    for _ in 0..integrity.budget {
+        let Some(pos) = integrity.pending.pop_front() else {
+            break;
+        };
+        integrity.queued.remove(&pos);
+
+        if is_supported(&world, pos) {
+            continue;
+        }
+        let Some(old_tile) = world.get_tile(pos).copied() else {
+            continue;
+        };
+
+        let landing = drop_rubble(&mut world, &gravity, pos, old_tile, &mut tile_events);
+        crush_column(&victims, &mut damage_events, pos, landing);
+
+        info!("Collapse at {:?}, rubble landed at {:?}", pos, landing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{Tile, TileKind};
+
+    const LEVEL: i32 = -10;
+
+    /// Open ground with grounded pillars of `pillar` at the given x, and
+    /// a row of `beam` from `from` to `to` along x at the same level
+    fn beam_world(
+        pillars: &[i32],
+        pillar: TileKind,
+        beam: TileKind,
+        from: i32,
+        to: i32,
+    ) -> GameWorld {
+        let mut world = GameWorld::new();
+        world.chunks.insert(IVec3::new(0, 0, -1), Default::default());
+        for x in pillars {
+            world.set_tile(IVec3::new(*x, 4, LEVEL - 1), Tile::from_kind(TileKind::STONE));
+            world.set_tile(IVec3::new(*x, 4, LEVEL), Tile::from_kind(pillar));
+        }
+        for x in from..=to {
+            world.set_tile(IVec3::new(x, 4, LEVEL), Tile::from_kind(beam));
+        }
+        world
+    }
+
+    /// Which beam tiles from `from` to `to` stand
+    fn standing(world: &GameWorld, from: i32, to: i32) -> Vec<bool> {
+        (from..=to)
+            .map(|x| is_supported(world, IVec3::new(x, 4, LEVEL)))
+            .collect()
+    }
+
+    #[test]
+    fn overhang_reaches_as_far_as_its_span() {
+        let stone = Tile::from_kind(TileKind::STONE).support_span();
+        let dirt = Tile::from_kind(TileKind::DIRT).support_span();
+        assert!(dirt < stone);
+
+        let world = beam_world(&[1], TileKind::STONE, TileKind::STONE, 2, 8);
+        let expected: Vec<bool> = (1..=7).map(|d| d <= stone).collect();
+        assert_eq!(standing(&world, 2, 8), expected);
+
+        let world = beam_world(&[1], TileKind::DIRT, TileKind::DIRT, 2, 8);
+        let expected: Vec<bool> = (1..=7).map(|d| d <= dirt).collect();
+        assert_eq!(standing(&world, 2, 8), expected);
+
+        // Dirt leaning on stone carries only its own span past the first tile
+        let world = beam_world(&[1], TileKind::STONE, TileKind::DIRT, 2, 8);
+        let expected: Vec<bool> = (1..=7).map(|d| d <= dirt + 1).collect();
+        assert_eq!(standing(&world, 2, 8), expected);
+    }
+
+    #[test]
+    fn bridge_between_pillars_spans_twice_as_far() {
+        let span = Tile::from_kind(TileKind::STONE).support_span();
+        let stone = TileKind::STONE;
+
+        let far = 2 * span + 1;
+        let world = beam_world(&[1, 1 + far], stone, stone, 2, far);
+        assert!(standing(&world, 2, far).iter().all(|stands| *stands));
+
+        let too_far = 2 * span + 2;
+        let world = beam_world(&[1, 1 + too_far], stone, stone, 2, too_far);
+        let middle = 1 + span + 1;
+        assert!(!is_supported(&world, IVec3::new(middle, 4, LEVEL)));
+        assert!(is_supported(&world, IVec3::new(middle - 1, 4, LEVEL)));
+    }
+}
@@ -0,0 +1,158 @@
+//! Tile change journal - who changed what, when, and why
+//!
+//! Every tile change is recorded with the frame it happened on, so
+//! an after-action review can replay how a wall came down or a tunnel
+//! flooded. Old entries roll off once the journal is full. F7 dumps it
+//! (see `save`), with the changes around each breach pulled out.
+
+use super::{ChangeCause, Tile, TileChanges};
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// One recorded tile change
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    /// Frame the change happened on
+    pub tick: u32,
+    pub position: IVec3,
+    pub old_tile: Tile,
+    pub new_tile: Tile,
+    pub cause: ChangeCause,
+    pub actor: Option<Entity>,
+}
+
+/// Rolling record of tile changes
+#[derive(Resource)]
+pub struct TileJournal {
+    entries: VecDeque<JournalEntry>,
+    /// Maximum entries kept
+    pub capacity: usize,
+}
+
+impl Default for TileJournal {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: 20_000,
+        }
+    }
+}
+
+impl TileJournal {
+    pub fn record(&mut self, entry: JournalEntry) {
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &JournalEntry> {
+        self.entries.iter()
+    }
+
+    /// Changes within `radius` tiles of `center` (for reviewing a breach)
+    pub fn around(
+        &self,
+        center: IVec3,
+        radius: i32,
+    ) -> impl DoubleEndedIterator<Item = &JournalEntry> {
+        self.entries
+            .iter()
+            .filter(move |e| (e.position - center).abs().max_element() <= radius)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Text dump of every entry, then the changes within `radius` of each
+    /// focus tile
+    pub fn export(&self, focus: &[IVec3], radius: i32) -> String {
+        let mut out = format!("# {} tile changes\n", self.entries.len());
+        out.extend(self.entries().map(entry_line));
+        for center in focus {
+            let (x, y, z) = (center.x, center.y, center.z);
+            out.push_str(&format!("\n# Within {} of {},{},{}\n", radius, x, y, z));
+            out.extend(self.around(*center, radius).map(entry_line));
+        }
+        out
+    }
+}
+
+/// Frame, position, glyph change, cause and actor of one entry
+fn entry_line(entry: &JournalEntry) -> String {
+    let pos = entry.position;
+    let actor = entry.actor.map_or(String::new(), |actor| format!(" {}", actor));
+    format!(
+        "{} {},{},{} {}>{} {:?}{}\n",
+        entry.tick,
+        pos.x,
+        pos.y,
+        pos.z,
+        entry.old_tile.to_ascii(),
+        entry.new_tile.to_ascii(),
+        entry.cause,
+        actor
+    )
+}
+
+/// System to record tile changes
+pub fn record_tile_changes(
+    mut journal: ResMut<TileJournal>,
+    mut events: TileChanges,
+    frame: Res<FrameCount>,
+) {
+    for event in events.read() {
+        journal.record(JournalEntry {
+            tick: frame.0,
+            position: event.position,
+            old_tile: event.old_tile,
+            new_tile: event.new_tile,
+            cause: event.cause,
+            actor: event.actor,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tick: u32, position: IVec3) -> JournalEntry {
+        JournalEntry {
+            tick,
+            position,
+            old_tile: Tile::Rubble,
+            new_tile: Tile::Air,
+            cause: ChangeCause::Dig,
+            actor: None,
+        }
+    }
+
+    #[test]
+    fn export_reviews_changes_around_each_focus() {
+        let mut journal = TileJournal {
+            capacity: 2,
+            ..default()
+        };
+        journal.record(entry(1, IVec3::new(0, 0, 0)));
+        journal.record(entry(2, IVec3::new(3, 0, 0)));
+        journal.record(entry(3, IVec3::new(40, 0, 0)));
+
+        let dump = journal.export(&[IVec3::new(2, 0, 0)], 1);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# 2 tile changes",
+                "2 3,0,0 %>. Dig",
+                "3 40,0,0 %>. Dig",
+                "",
+                "# Within 1 of 2,0,0",
+                "2 3,0,0 %>. Dig",
+            ]
+        );
+    }
+}
@@ -3,13 +3,42 @@
 //! The world is a 3D grid rendered as 2D layers. Each tile has HP which
 //! doubles as traversal cost for flow field pathfinding.
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
+mod ascii;
 mod chunk;
+mod deposits;
+mod enclosure;
+mod environment;
+mod fire;
+mod fluid;
+mod generation;
+mod gravity;
+mod integrity;
+mod journal;
+mod region;
+mod registry;
+mod streaming;
+mod tick;
 mod tile;
 mod z_level;
 
+pub use ascii::*;
 pub use chunk::*;
+pub use deposits::*;
+pub use enclosure::*;
+pub use environment::*;
+pub use fire::*;
+pub use fluid::*;
+pub use generation::*;
+pub use gravity::*;
+pub use integrity::*;
+pub use journal::*;
+pub use region::*;
+pub use registry::*;
+pub use streaming::*;
+pub use tick::*;
 pub use tile::*;
 pub use z_level::*;
 
@@ -17,10 +46,45 @@ pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<GameWorld>()
+        if !install_tile_registry(TileRegistry::load(TILE_REGISTRY_PATH)) {
+            warn!("Tile registry was already in use; keeping it");
+        }
+        app.insert_resource(tile_registry().clone())
+            .init_resource::<GameWorld>()
             .init_resource::<CurrentZLevel>()
+            .init_resource::<Enclosure>()
+            .init_resource::<WorldGenConfig>()
+            .init_resource::<NestSites>()
+            .init_resource::<ChunkStreaming>()
+            .init_resource::<StreamingAnchors>()
+            .init_resource::<StructuralIntegrity>()
+            .init_resource::<RubbleGravity>()
+            .init_resource::<WaterField>()
+            .init_resource::<FireField>()
+            .init_resource::<TileJournal>()
+            .init_resource::<EnvironmentField>()
+            .init_resource::<Deposits>()
+            .init_resource::<SecondTick>()
             .add_event::<TileChangedEvent>()
-            .add_systems(Startup, setup_world);
+            .add_event::<ChunkChangedEvent>()
+            .add_event::<ChunkLoadedEvent>()
+            .add_event::<ChunkUnloadedEvent>()
+            .add_systems(Startup, setup_world)
+            .add_systems(First, advance_second_tick)
+            .add_systems(
+                Update,
+                (
+                    (stream_chunks, update_enclosure).chain(),
+                    (queue_support_checks, collapse_unsupported).chain(),
+                    (queue_rubble_checks, settle_rubble).chain(),
+                    (queue_water_updates, simulate_water, flood_tunnels).chain(),
+                    drown_ants,
+                    (ignite_from_flamers, spread_fire, catch_fire, burn_entities).chain(),
+                    record_tile_changes,
+                    detonate_gas,
+                    forget_replaced_deposits,
+                ),
+            );
     }
 }
 
@@ -57,6 +121,15 @@ impl GameWorld {
             .or_insert_with(Chunk::new)
             .set_tile(local_pos.as_uvec3(), tile);
     }
+
+    /// Inclusive min/max tile positions covered by loaded chunks
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        let size = IVec3::splat(CHUNK_SIZE as i32);
+        let mut keys = self.chunks.keys();
+        let first = *keys.next()?;
+        let (min, max) = keys.fold((first, first), |(min, max), c| (min.min(*c), max.max(*c)));
+        Some((min * size, (max + IVec3::ONE) * size - IVec3::ONE))
+    }
 }
 
 /// Event fired when a tile changes (for flow field recalculation)
@@ -65,36 +138,93 @@ pub struct TileChangedEvent {
     pub position: IVec3,
     pub old_tile: Tile,
     pub new_tile: Tile,
+    pub cause: ChangeCause,
+    /// Entity responsible, if any (None for the player's own orders and the
+    /// environment)
+    pub actor: Option<Entity>,
 }
 
-fn setup_world(mut world: ResMut<GameWorld>) {
-    // Generate initial world with surface and underground
-    // Surface at Z=0, underground at Z=-1, Z=-2, etc.
+/// Event fired once per chunk for a batch of tile changes (see `WorldEdit`)
+#[derive(Event)]
+pub struct ChunkChangedEvent {
+    pub chunk: IVec3,
+    pub changes: Vec<TileChangedEvent>,
+}
 
-    let world_size = 32; // 32x32 tiles for now
+/// Event fired when a chunk is generated or loaded into memory
+#[derive(Event)]
+pub struct ChunkLoadedEvent {
+    pub chunk: IVec3,
+}
 
-    for x in 0..world_size {
-        for y in 0..world_size {
-            // Surface layer - mostly air with some walls
-            world.set_tile(IVec3::new(x, y, 0), Tile::Air);
+/// Event fired when a chunk is persisted and dropped from memory
+#[derive(Event)]
+pub struct ChunkUnloadedEvent {
+    pub chunk: IVec3,
+}
 
-            // Underground layers - dirt and stone
-            world.set_tile(
-                IVec3::new(x, y, -1),
-                Tile::Dirt {
-                    hp: 50,
-                    max_hp: 50,
-                },
-            );
-            world.set_tile(
-                IVec3::new(x, y, -2),
-                Tile::Stone {
-                    hp: 100,
-                    max_hp: 100,
-                },
-            );
-        }
+/// Reads tile changes whether they were sent one at a time or batched
+/// per chunk
+#[derive(SystemParam)]
+pub struct TileChanges<'w, 's> {
+    single: EventReader<'w, 's, TileChangedEvent>,
+    batched: EventReader<'w, 's, ChunkChangedEvent>,
+}
+
+impl TileChanges<'_, '_> {
+    pub fn read(&mut self) -> impl Iterator<Item = &TileChangedEvent> {
+        self.single
+            .read()
+            .chain(self.batched.read().flat_map(|event| event.changes.iter()))
+    }
+
+    /// Chunks that changed: one per batch, or one per single change
+    pub fn chunks(&mut self) -> impl Iterator<Item = IVec3> + '_ {
+        self.single
+            .read()
+            .map(|event| chunk_position(event.position))
+            .chain(self.batched.read().map(|event| event.chunk))
+    }
+
+    pub fn clear(&mut self) {
+        self.single.clear();
+        self.batched.clear();
     }
+}
+
+/// Why a tile changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeCause {
+    Build,
+    Dig,
+    Damage,
+    /// Cave-ins and falling rubble
+    Collapse,
+    Undo,
+}
+
+pub fn setup_world(
+    mut world: ResMut<GameWorld>,
+    config: Res<WorldGenConfig>,
+    mut streaming: ResMut<ChunkStreaming>,
+    mut nest_sites: ResMut<NestSites>,
+    mut loaded_events: EventWriter<ChunkLoadedEvent>,
+) {
+    // Surface at Z=0, underground at Z=-1, Z=-2, etc.
+    // Only the starting area is generated up front; the rest streams in.
+    streaming.reset();
+    let spawn = config.spawn_point(world.surface_z);
+    let nests = generate_area(&config, &mut world, spawn, streaming.load_radius);
+    nest_sites.sites.extend(nests);
+    loaded_events.send_batch(world.chunks.keys().map(|chunk| ChunkLoadedEvent { chunk: *chunk }));
 
-    info!("World generated: {}x{} tiles, 3 Z-levels", world_size, world_size);
+    info!(
+        "World generated: seed {:#x}, {}x{} tiles, {} Z-levels deep, {} chunks loaded, {} nests",
+        config.seed,
+        config.size.x,
+        config.size.y,
+        config.depth,
+        world.chunks.len(),
+        nest_sites.sites.len()
+    );
 }
@@ -0,0 +1,221 @@
+//! Region queries and bulk edits
+//!
+//! `Region` describes a box or sphere of tiles. `GameWorld` can iterate the
+//! loaded tiles in a region chunk by chunk and fill it in one pass, and a
+//! `WorldEdit` batches many changes into one `ChunkChangedEvent` per chunk
+//! instead of a `TileChangedEvent` per tile.
+
+use super::{ChangeCause, Chunk, ChunkChangedEvent, GameWorld, Tile, TileChangedEvent, CHUNK_SIZE};
+use bevy::prelude::*;
+
+const SIZE: i32 = CHUNK_SIZE as i32;
+
+/// The 6-neighbourhood used for movement (4 horizontal + up/down a Z-level)
+pub const NEIGHBORS: [IVec3; 6] = [
+    IVec3::new(1, 0, 0),
+    IVec3::new(-1, 0, 0),
+    IVec3::new(0, 1, 0),
+    IVec3::new(0, -1, 0),
+    IVec3::new(0, 0, 1),
+    IVec3::new(0, 0, -1),
+];
+
+/// The horizontal part of `NEIGHBORS` (same Z-level)
+pub const HORIZONTAL: [IVec3; 4] = [NEIGHBORS[0], NEIGHBORS[1], NEIGHBORS[2], NEIGHBORS[3]];
+
+/// A set of tile positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// Every tile from `min` to `max` inclusive
+    Cuboid { min: IVec3, max: IVec3 },
+    /// Every tile within `radius` of `center`
+    Sphere { center: IVec3, radius: i32 },
+}
+
+/// Positions from `min` to `max` inclusive, x-major
+fn box_positions(min: IVec3, max: IVec3) -> impl Iterator<Item = IVec3> {
+    (min.x..=max.x).flat_map(move |x| {
+        (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+    })
+}
+
+impl Region {
+    /// Box spanning two corners, in any order
+    pub fn cuboid(a: IVec3, b: IVec3) -> Self {
+        Region::Cuboid {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Every tile of one chunk
+    pub fn chunk(chunk: IVec3) -> Self {
+        let min = chunk * SIZE;
+        Region::Cuboid {
+            min,
+            max: min + IVec3::splat(SIZE - 1),
+        }
+    }
+
+    pub fn sphere(center: IVec3, radius: i32) -> Self {
+        Region::Sphere {
+            center,
+            radius: radius.max(0),
+        }
+    }
+
+    /// Inclusive bounding box
+    pub fn bounds(&self) -> (IVec3, IVec3) {
+        match *self {
+            Region::Cuboid { min, max } => (min, max),
+            Region::Sphere { center, radius } => {
+                (center - IVec3::splat(radius), center + IVec3::splat(radius))
+            }
+        }
+    }
+
+    pub fn contains(&self, pos: IVec3) -> bool {
+        match *self {
+            Region::Cuboid { min, max } => pos.cmpge(min).all() && pos.cmple(max).all(),
+            Region::Sphere { center, radius } => (pos - center).length_squared() <= radius * radius,
+        }
+    }
+
+    /// Every position in the region
+    pub fn positions(self) -> impl Iterator<Item = IVec3> {
+        let (min, max) = self.bounds();
+        box_positions(min, max).filter(move |pos| self.contains(*pos))
+    }
+
+    /// Every chunk the region touches
+    pub fn chunks(self) -> impl Iterator<Item = IVec3> {
+        let (min, max) = self.bounds();
+        box_positions(
+            min.div_euclid(IVec3::splat(SIZE)),
+            max.div_euclid(IVec3::splat(SIZE)),
+        )
+    }
+
+    /// The region's positions inside one chunk
+    fn positions_in_chunk(self, chunk: IVec3) -> impl Iterator<Item = IVec3> {
+        let origin = chunk * SIZE;
+        let (min, max) = self.bounds();
+        box_positions(min.max(origin), max.min(origin + IVec3::splat(SIZE - 1)))
+            .filter(move |pos| self.contains(*pos))
+    }
+
+    /// Does the region cover all of a chunk?
+    fn covers_chunk(&self, chunk: IVec3) -> bool {
+        let origin = chunk * SIZE;
+        let corner = origin + IVec3::splat(SIZE - 1);
+        match *self {
+            Region::Cuboid { min, max } => origin.cmpge(min).all() && corner.cmple(max).all(),
+            // A chunk is convex, so all eight corners inside means all of it is
+            Region::Sphere { .. } => box_positions(IVec3::ZERO, IVec3::ONE)
+                .all(|c| self.contains(origin + c * (SIZE - 1))),
+        }
+    }
+}
+
+impl GameWorld {
+    /// Loaded tiles in a region, a chunk at a time
+    pub fn tiles_in(&self, region: Region) -> impl Iterator<Item = (IVec3, &Tile)> {
+        region
+            .chunks()
+            .filter_map(|chunk_pos| Some((chunk_pos, self.chunks.get(&chunk_pos)?)))
+            .flat_map(move |(chunk_pos, chunk)| {
+                let origin = chunk_pos * SIZE;
+                region
+                    .positions_in_chunk(chunk_pos)
+                    .map(move |pos| (pos, chunk.get_tile((pos - origin).as_uvec3())))
+            })
+    }
+
+    /// Set every loaded tile in a region. Returns how many tiles changed.
+    ///
+    /// Chunks the region covers completely are replaced outright, so
+    /// filling large areas stays cheap.
+    pub fn fill_region(&mut self, region: Region, tile: Tile) -> usize {
+        let mut changed = 0;
+        for chunk_pos in region.chunks() {
+            let Some(chunk) = self.chunks.get_mut(&chunk_pos) else {
+                continue;
+            };
+
+            if region.covers_chunk(chunk_pos) {
+                if chunk.uniform_tile() == Some(&tile) {
+                    continue;
+                }
+                changed += chunk.iter_tiles().filter(|(_, old)| **old != tile).count();
+                *chunk = Chunk::filled(tile);
+                continue;
+            }
+
+            let origin = chunk_pos * SIZE;
+            for pos in region.positions_in_chunk(chunk_pos) {
+                let local = (pos - origin).as_uvec3();
+                if *chunk.get_tile(local) != tile {
+                    chunk.set_tile(local, tile);
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Start a batch of changes with a shared cause
+    pub fn edit(&mut self, cause: ChangeCause, actor: Option<Entity>) -> WorldEdit<'_> {
+        WorldEdit {
+            world: self,
+            cause,
+            actor,
+            changes: hashbrown::HashMap::new(),
+        }
+    }
+}
+
+/// A batch of tile changes, reported per chunk when finished
+///
+/// Changes apply to the world immediately; only the notification is
+/// deferred. Send the events from `finish` so other systems see the edit.
+pub struct WorldEdit<'w> {
+    world: &'w mut GameWorld,
+    cause: ChangeCause,
+    actor: Option<Entity>,
+    changes: hashbrown::HashMap<IVec3, Vec<TileChangedEvent>>,
+}
+
+impl WorldEdit<'_> {
+    /// The world as edited so far
+    pub fn world(&self) -> &GameWorld {
+        self.world
+    }
+
+    /// Set one tile. Returns whether it changed.
+    pub fn set_tile(&mut self, pos: IVec3, tile: Tile) -> bool {
+        let old_tile = self.world.get_tile(pos).copied();
+        if old_tile == Some(tile) {
+            return false;
+        }
+        self.world.set_tile(pos, tile);
+        self.changes
+            .entry(pos.div_euclid(IVec3::splat(SIZE)))
+            .or_default()
+            .push(TileChangedEvent {
+                position: pos,
+                old_tile: old_tile.unwrap_or(Tile::Air),
+                new_tile: tile,
+                cause: self.cause,
+                actor: self.actor,
+            });
+        true
+    }
+
+    /// Finish the batch, returning one event per changed chunk
+    pub fn finish(self) -> Vec<ChunkChangedEvent> {
+        self.changes
+            .into_iter()
+            .map(|(chunk, changes)| ChunkChangedEvent { chunk, changes })
+            .collect()
+    }
+}
@@ -0,0 +1,382 @@
+//! Tile registry - per-kind tile data loaded from `assets/tiles.ron`
+//!
+//! HP, traversal cost, diggability, yields, glyph and colour all live in
+//! the data file so new materials can be added without touching code. The
+//! built-in kinds always occupy the first ids: most back a dedicated `Tile`
+//! variant, the rest are `Tile::Material` kinds code can rely on. Any
+//! further entries become `Tile::Material` kinds too.
+//!
+//! `WorldPlugin` loads the file once and inserts the registry as a
+//! resource. Per-tile lookups like `Tile::def` have no access to the ECS,
+//! so the same registry is also installed behind `tile_registry`, which
+//! falls back to the embedded copy until then (in tests and tools).
+
+use super::{AntStructureType, BuildMaterial, OreType};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Registry file read at startup
+pub const TILE_REGISTRY_PATH: &str = "assets/tiles.ron";
+
+/// Copy of the registry baked into the binary, used when the file is
+/// missing or broken and to fill in built-in kinds the file leaves out
+const DEFAULT_REGISTRY: &str = include_str!("../../assets/tiles.ron");
+
+/// Names of the built-in kinds, in id order
+const BUILTIN_NAMES: [&str; 16] = [
+    "air",
+    "dirt",
+    "stone",
+    "iron_ore",
+    "tungsten_ore",
+    "wood_wall",
+    "stone_wall",
+    "metal_wall",
+    "wood_floor",
+    "stone_floor",
+    "metal_floor",
+    "rubble",
+    "ant_tunnel",
+    "ant_nest",
+    "ant_storage",
+    "rubble_plug",
+];
+
+/// Index of a tile kind in the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileKind(pub u16);
+
+impl TileKind {
+    pub const AIR: TileKind = TileKind(0);
+    pub const DIRT: TileKind = TileKind(1);
+    pub const STONE: TileKind = TileKind(2);
+    pub const IRON_ORE: TileKind = TileKind(3);
+    pub const TUNGSTEN_ORE: TileKind = TileKind(4);
+    pub const WOOD_WALL: TileKind = TileKind(5);
+    pub const STONE_WALL: TileKind = TileKind(6);
+    pub const METAL_WALL: TileKind = TileKind(7);
+    pub const WOOD_FLOOR: TileKind = TileKind(8);
+    pub const STONE_FLOOR: TileKind = TileKind(9);
+    pub const METAL_FLOOR: TileKind = TileKind(10);
+    pub const RUBBLE: TileKind = TileKind(11);
+    pub const ANT_TUNNEL: TileKind = TileKind(12);
+    pub const ANT_NEST: TileKind = TileKind(13);
+    pub const ANT_STORAGE: TileKind = TileKind(14);
+    /// Rubble packed into a shaft (a `Tile::Material`)
+    pub const RUBBLE_PLUG: TileKind = TileKind(15);
+
+    pub fn ore(ore: OreType) -> TileKind {
+        match ore {
+            OreType::Iron => TileKind::IRON_ORE,
+            OreType::Tungsten => TileKind::TUNGSTEN_ORE,
+        }
+    }
+
+    pub fn wall(material: BuildMaterial) -> TileKind {
+        match material {
+            BuildMaterial::Wood => TileKind::WOOD_WALL,
+            BuildMaterial::Stone => TileKind::STONE_WALL,
+            BuildMaterial::Metal => TileKind::METAL_WALL,
+        }
+    }
+
+    pub fn floor(material: BuildMaterial) -> TileKind {
+        match material {
+            BuildMaterial::Wood => TileKind::WOOD_FLOOR,
+            BuildMaterial::Stone => TileKind::STONE_FLOOR,
+            BuildMaterial::Metal => TileKind::METAL_FLOOR,
+        }
+    }
+
+    pub fn ant_structure(structure_type: AntStructureType) -> TileKind {
+        match structure_type {
+            AntStructureType::Tunnel => TileKind::ANT_TUNNEL,
+            AntStructureType::Nest => TileKind::ANT_NEST,
+            AntStructureType::Storage => TileKind::ANT_STORAGE,
+        }
+    }
+
+    /// Is this one of the kinds every registry defines?
+    pub fn is_builtin(&self) -> bool {
+        (self.0 as usize) < BUILTIN_NAMES.len()
+    }
+}
+
+/// Resources granted for digging out a tile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TileYield {
+    pub tungsten: u32,
+    pub iron: u32,
+    pub wood: u32,
+}
+
+impl TileYield {
+    pub fn total(&self) -> u32 {
+        self.tungsten + self.iron + self.wood
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Take up to `units` out, rarest first
+    pub fn take(&mut self, units: u32) -> TileYield {
+        let mut left = units;
+        let mut draw = |amount: &mut u32| {
+            let taken = (*amount).min(left);
+            *amount -= taken;
+            left -= taken;
+            taken
+        };
+        TileYield {
+            tungsten: draw(&mut self.tungsten),
+            iron: draw(&mut self.iron),
+            wood: draw(&mut self.wood),
+        }
+    }
+
+    pub fn add(&mut self, other: TileYield) {
+        self.tungsten += other.tungsten;
+        self.iron += other.iron;
+        self.wood += other.wood;
+    }
+}
+
+/// Data for one tile kind
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TileDef {
+    pub name: String,
+    /// Full HP (0 for tiles without HP)
+    pub hp: u16,
+    /// Flat traversal cost
+    pub cost: u32,
+    /// Traversal cost added per point of current HP
+    pub cost_per_hp: f32,
+    pub passable: bool,
+    pub diggable: bool,
+    pub destructible: bool,
+    /// Holds up the tile above it
+    pub structural: bool,
+    /// Tiles it can span sideways without support below
+    pub span: i32,
+    /// Water that can seep through per flow step (passable tiles flow freely)
+    pub seep: u8,
+    /// Chance per second of catching fire from a burning neighbour
+    pub flammability: f32,
+    /// Total resources the tile holds
+    pub yields: TileYield,
+    /// Kind left behind once the yield is mined out (air if unset)
+    pub depletes_to: Option<String>,
+    pub glyph: char,
+    pub color: (f32, f32, f32),
+    /// Colour at zero HP, blended toward `color` as HP rises
+    pub damaged_color: Option<(f32, f32, f32)>,
+    /// Colour used below the surface instead of `color`
+    pub underground_color: Option<(f32, f32, f32)>,
+}
+
+impl Default for TileDef {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            hp: 0,
+            cost: 0,
+            cost_per_hp: 0.0,
+            passable: false,
+            diggable: false,
+            destructible: false,
+            structural: false,
+            span: 0,
+            seep: 0,
+            flammability: 0.0,
+            yields: TileYield::default(),
+            depletes_to: None,
+            glyph: '?',
+            color: (1.0, 0.0, 1.0),
+            damaged_color: None,
+            underground_color: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RegistryFile {
+    tiles: Vec<TileDef>,
+}
+
+/// All tile kinds, indexed by `TileKind`
+#[derive(Resource, Clone)]
+pub struct TileRegistry {
+    defs: Vec<TileDef>,
+    /// Stand-in for kinds the registry doesn't define
+    unknown: TileDef,
+    by_name: hashbrown::HashMap<String, TileKind>,
+    by_glyph: hashbrown::HashMap<char, TileKind>,
+    max_span: i32,
+}
+
+impl TileRegistry {
+    /// Build a registry from RON source, filling in any built-in kinds it
+    /// lacks from the embedded default
+    pub fn from_ron(source: &str) -> Result<Self, ron::error::SpannedError> {
+        let file: RegistryFile = ron::from_str(source)?;
+        let mut fallback: Vec<TileDef> = Vec::new();
+        if source != DEFAULT_REGISTRY {
+            fallback = ron::from_str::<RegistryFile>(DEFAULT_REGISTRY)
+                .map(|f| f.tiles)
+                .unwrap_or_default();
+        }
+
+        let mut defs: Vec<TileDef> = BUILTIN_NAMES
+            .iter()
+            .map(|name| {
+                file.tiles
+                    .iter()
+                    .chain(fallback.iter())
+                    .find(|def| def.name == *name)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        warn!("Tile registry is missing built-in kind '{}'", name);
+                        TileDef {
+                            name: name.to_string(),
+                            ..default()
+                        }
+                    })
+            })
+            .collect();
+        defs.extend(
+            file.tiles
+                .into_iter()
+                .filter(|def| !BUILTIN_NAMES.contains(&def.name.as_str())),
+        );
+
+        let mut by_name = hashbrown::HashMap::new();
+        let mut by_glyph = hashbrown::HashMap::new();
+        for (index, def) in defs.iter().enumerate() {
+            if by_name
+                .insert(def.name.clone(), TileKind(index as u16))
+                .is_some()
+            {
+                warn!("Tile registry defines '{}' more than once", def.name);
+            }
+            // First kind wins so ASCII maps stay unambiguous
+            if let Some(other) = by_glyph.get(&def.glyph) {
+                warn!(
+                    "Tile '{}' reuses glyph '{}' of kind {:?}",
+                    def.name, def.glyph, other
+                );
+            } else {
+                by_glyph.insert(def.glyph, TileKind(index as u16));
+            }
+        }
+        let max_span = defs.iter().map(|def| def.span).max().unwrap_or(0);
+
+        Ok(Self {
+            defs,
+            unknown: TileDef {
+                name: "unknown".to_string(),
+                ..default()
+            },
+            by_name,
+            by_glyph,
+            max_span,
+        })
+    }
+
+    /// The registry baked into the binary
+    pub fn embedded() -> Self {
+        Self::from_ron(DEFAULT_REGISTRY).expect("embedded tile registry is valid")
+    }
+
+    /// Load a registry file, falling back to the embedded default
+    pub fn load(path: &str) -> Self {
+        let loaded = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| Self::from_ron(&source).map_err(|err| err.to_string()));
+        match loaded {
+            Ok(registry) => {
+                info!("Loaded {} tile kinds from {}", registry.defs.len(), path);
+                registry
+            }
+            Err(err) => {
+                warn!("Using built-in tile registry, {} unavailable: {}", path, err);
+                Self::embedded()
+            }
+        }
+    }
+
+    /// Data for a kind, if the registry defines it
+    pub fn def(&self, kind: TileKind) -> Option<&TileDef> {
+        self.defs.get(kind.0 as usize)
+    }
+
+    /// Data for a kind, or an impassable placeholder if it is undefined
+    pub fn def_or_unknown(&self, kind: TileKind) -> &TileDef {
+        self.def(kind).unwrap_or(&self.unknown)
+    }
+
+    /// Look up a kind by name
+    pub fn kind(&self, name: &str) -> Option<TileKind> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Look up a kind by ASCII glyph
+    pub fn kind_by_glyph(&self, glyph: char) -> Option<TileKind> {
+        self.by_glyph.get(&glyph).copied()
+    }
+
+    /// Longest support span of any kind
+    pub fn max_span(&self) -> i32 {
+        self.max_span
+    }
+}
+
+static REGISTRY: OnceLock<TileRegistry> = OnceLock::new();
+
+/// Make `registry` the one behind per-tile lookups. Fails if tiles were
+/// already looked up (or another registry installed) before this call.
+pub fn install_tile_registry(registry: TileRegistry) -> bool {
+    REGISTRY.set(registry).is_ok()
+}
+
+/// The registry behind per-tile lookups
+pub fn tile_registry() -> &'static TileRegistry {
+    REGISTRY.get_or_init(TileRegistry::embedded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_registry_parses_with_every_builtin() {
+        let registry = TileRegistry::embedded();
+        for (id, name) in BUILTIN_NAMES.iter().enumerate() {
+            let def = registry.def(TileKind(id as u16)).unwrap();
+            assert_eq!(def.name, *name);
+        }
+        assert!(registry.kind("tree").is_some());
+    }
+
+    #[test]
+    fn names_and_glyphs_round_trip() {
+        let registry = TileRegistry::embedded();
+        for (id, def) in registry.defs.iter().enumerate() {
+            let kind = TileKind(id as u16);
+            assert_eq!(registry.kind(&def.name), Some(kind));
+            assert_eq!(registry.kind_by_glyph(def.glyph), Some(kind), "glyph of {}", def.name);
+        }
+    }
+
+    #[test]
+    fn undefined_kinds_are_impassable_placeholders() {
+        let registry = TileRegistry::embedded();
+        let undefined = TileKind(registry.defs.len() as u16);
+        assert!(registry.def(undefined).is_none());
+        let def = registry.def_or_unknown(undefined);
+        assert!(!def.passable && !def.destructible);
+    }
+}
@@ -0,0 +1,210 @@
+//! Chunk streaming - generate and load chunks near the action
+//!
+//! Anchors are the camera, player structures, away team members and active
+//! ant leaders, published into `StreamingAnchors` by the modules that own
+//! them. Chunks near any anchor are loaded from disk (or generated if they
+//! were never visited); chunks far from every anchor are written to disk and
+//! dropped from memory.
+//!
+//! The chunk directory is scratch space for the game in progress: it is
+//! wiped on a new game and on load, and saves fold its chunks back in.
+
+use super::{ChunkLoadedEvent, ChunkUnloadedEvent, GameWorld, NestSites, WorldGenConfig, CHUNK_SIZE};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Chunk streaming settings and bookkeeping
+#[derive(Resource)]
+pub struct ChunkStreaming {
+    /// Horizontal radius (in chunks) loaded around each anchor
+    pub load_radius: i32,
+    /// Chunks further than this (in chunks) from every anchor are unloaded
+    pub unload_radius: i32,
+    /// Vertical radius (in chunks) loaded around each anchor
+    pub vertical_radius: i32,
+    /// Maximum chunks loaded or generated per update
+    pub budget: usize,
+    /// Seconds between streaming passes
+    pub interval: f32,
+    /// Directory unloaded chunks are written to
+    pub directory: PathBuf,
+    /// Chunks of this game currently held on disk rather than in memory
+    persisted: hashbrown::HashSet<IVec3>,
+    timer: f32,
+}
+
+impl Default for ChunkStreaming {
+    fn default() -> Self {
+        Self {
+            load_radius: 2,
+            unload_radius: 4,
+            vertical_radius: 1,
+            budget: 4,
+            interval: 0.5,
+            directory: PathBuf::from("saves/chunks"),
+            persisted: hashbrown::HashSet::new(),
+            timer: 0.0,
+        }
+    }
+}
+
+impl ChunkStreaming {
+    /// File a chunk is persisted to
+    pub fn chunk_path(&self, chunk: IVec3) -> PathBuf {
+        self.directory
+            .join(format!("{}_{}_{}.chunk", chunk.x, chunk.y, chunk.z))
+    }
+
+    /// Chunks of this game currently held on disk
+    pub fn persisted(&self) -> impl Iterator<Item = &IVec3> {
+        self.persisted.iter()
+    }
+
+    /// Forget every persisted chunk and delete their files
+    pub fn reset(&mut self) {
+        self.persisted.clear();
+        match std::fs::remove_dir_all(&self.directory) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => warn!("Failed to clear streamed chunks in {:?}: {}", self.directory, err),
+        }
+    }
+}
+
+/// Who published a set of streaming anchors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnchorSource {
+    Camera,
+    Player,
+    Ants,
+}
+
+/// Tiles chunks are streamed around
+///
+/// Each source replaces its own anchors whenever it publishes, so the world
+/// never has to know about cameras, structures or ants.
+#[derive(Resource, Default)]
+pub struct StreamingAnchors {
+    tiles: hashbrown::HashMap<AnchorSource, Vec<IVec3>>,
+}
+
+impl StreamingAnchors {
+    /// Replace the anchors from one source
+    pub fn set(&mut self, source: AnchorSource, tiles: impl IntoIterator<Item = IVec3>) {
+        let anchors = self.tiles.entry(source).or_default();
+        anchors.clear();
+        anchors.extend(tiles);
+    }
+
+    /// Every anchor tile from every source
+    pub fn tiles(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.tiles.values().flatten().copied()
+    }
+}
+
+/// Events announcing chunks streamed in and out
+#[derive(SystemParam)]
+pub struct ChunkStreamEvents<'w> {
+    loaded: EventWriter<'w, ChunkLoadedEvent>,
+    unloaded: EventWriter<'w, ChunkUnloadedEvent>,
+}
+
+/// System to load chunks near anchors and unload distant ones
+pub fn stream_chunks(
+    mut world: ResMut<GameWorld>,
+    mut streaming: ResMut<ChunkStreaming>,
+    mut nest_sites: ResMut<NestSites>,
+    mut events: ChunkStreamEvents,
+    anchors: Res<StreamingAnchors>,
+    config: Res<WorldGenConfig>,
+    time: Res<Time>,
+) {
+    streaming.timer += time.delta_secs();
+    if streaming.timer < streaming.interval {
+        return;
+    }
+    streaming.timer = 0.0;
+
+    // Chunks containing each anchor
+    let mut anchor_chunks: Vec<IVec3> = anchors.tiles().map(chunk_position).collect();
+    anchor_chunks.sort_by_key(|c| c.to_array());
+    anchor_chunks.dedup();
+    if anchor_chunks.is_empty() {
+        return;
+    }
+
+    let near = |chunk: IVec3, radius: i32| {
+        anchor_chunks.iter().any(|anchor| {
+            let d = (chunk - *anchor).abs();
+            d.x <= radius && d.y <= radius && d.z <= streaming.vertical_radius.max(radius)
+        })
+    };
+
+    // Unload what no anchor is near, persisting it first
+    let far: Vec<IVec3> = world
+        .chunks
+        .keys()
+        .filter(|chunk| !near(**chunk, streaming.unload_radius))
+        .copied()
+        .collect();
+    for chunk_pos in far {
+        let path = streaming.chunk_path(chunk_pos);
+        let Some(chunk) = world.chunks.get(&chunk_pos) else {
+            continue;
+        };
+        match crate::save::write_chunk_file(&path, chunk) {
+            Ok(()) => {
+                world.chunks.remove(&chunk_pos);
+                streaming.persisted.insert(chunk_pos);
+                events.unloaded.send(ChunkUnloadedEvent { chunk: chunk_pos });
+            }
+            Err(err) => warn!("Keeping chunk {:?} loaded, failed to persist: {}", chunk_pos, err),
+        }
+    }
+
+    // Load or generate what anchors need, nearest anchors first
+    let (min, max) = config.chunk_range(world.surface_z);
+    let radius = streaming.load_radius;
+    let vertical = streaming.vertical_radius;
+    let mut loaded = 0;
+    'anchors: for anchor in &anchor_chunks {
+        for z in (anchor.z - vertical).max(min.z)..=(anchor.z + vertical).min(max.z) {
+            for x in (anchor.x - radius).max(min.x)..=(anchor.x + radius).min(max.x) {
+                for y in (anchor.y - radius).max(min.y)..=(anchor.y + radius).min(max.y) {
+                    if loaded >= streaming.budget {
+                        break 'anchors;
+                    }
+                    let chunk_pos = IVec3::new(x, y, z);
+                    if world.chunks.contains_key(&chunk_pos) {
+                        continue;
+                    }
+
+                    if streaming.persisted.remove(&chunk_pos) {
+                        let path = streaming.chunk_path(chunk_pos);
+                        match crate::save::read_chunk_file(&path) {
+                            Ok(chunk) => {
+                                world.chunks.insert(chunk_pos, chunk);
+                            }
+                            Err(err) => {
+                                // Its nest (if any) was recorded when first generated
+                                warn!("Regenerating chunk {:?}: {}", chunk_pos, err);
+                                config.generate_into(&mut world, chunk_pos);
+                            }
+                        }
+                    } else {
+                        let nest = config.generate_into(&mut world, chunk_pos);
+                        nest_sites.sites.extend(nest);
+                    }
+                    events.loaded.send(ChunkLoadedEvent { chunk: chunk_pos });
+                    loaded += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Chunk coordinate of a world tile position
+pub fn chunk_position(pos: IVec3) -> IVec3 {
+    pos.div_euclid(IVec3::splat(CHUNK_SIZE as i32))
+}
@@ -0,0 +1,38 @@
+//! Once-a-second tick for effects dealt out in whole units
+//!
+//! Ant HP and resources are whole numbers, so a sliver of drowning,
+//! burning or mining per frame would round away to nothing. Systems like
+//! these wait for the shared tick and apply a second's worth at once.
+
+use bevy::prelude::*;
+
+/// Shared once-a-second tick, advanced at the start of each frame
+#[derive(Resource, Default)]
+pub struct SecondTick {
+    timer: f32,
+    /// Seconds covered by the tick that just fired (0 between ticks)
+    elapsed: f32,
+}
+
+impl SecondTick {
+    /// Did the tick fire this frame?
+    pub fn fired(&self) -> bool {
+        self.elapsed > 0.0
+    }
+
+    /// Seconds since the previous tick, if it fired this frame
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+}
+
+/// System to advance the shared tick
+pub fn advance_second_tick(mut tick: ResMut<SecondTick>, time: Res<Time>) {
+    tick.timer += time.delta_secs();
+    if tick.timer < 1.0 {
+        tick.elapsed = 0.0;
+        return;
+    }
+    tick.elapsed = tick.timer;
+    tick.timer = 0.0;
+}
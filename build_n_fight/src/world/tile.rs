@@ -2,7 +2,11 @@
 //!
 //! Key insight: Wall traversal cost = Wall HP
 //! Damaged walls naturally attract more ants.
+//!
+//! Per-kind numbers (HP, costs, glyphs, colours) come from the tile
+//! registry; the variants here only carry per-tile state.
 
+use super::{tile_registry, TileDef, TileKind};
 use bevy::prelude::*;
 
 /// BuildMaterial types for constructed tiles
@@ -16,14 +20,18 @@ pub enum BuildMaterial {
 
 impl BuildMaterial {
     pub fn base_hp(&self) -> u16 {
-        match self {
-            BuildMaterial::Wood => 50,
-            BuildMaterial::Stone => 150,
-            BuildMaterial::Metal => 300,
-        }
+        tile_registry().def_or_unknown(TileKind::wall(*self)).hp
     }
 }
 
+/// Ore carried by ore-bearing rock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OreType {
+    #[default]
+    Iron,
+    Tungsten,
+}
+
 /// Tile types in the world
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Tile {
@@ -42,6 +50,13 @@ pub enum Tile {
         max_hp: u16,
     },
 
+    /// Ore-bearing rock - dig it out for resources
+    Ore {
+        hp: u16,
+        max_hp: u16,
+        ore: OreType,
+    },
+
     /// Player-built wall
     Wall {
         hp: u16,
@@ -64,6 +79,9 @@ pub enum Tile {
         hp: u16,
         structure_type: AntStructureType,
     },
+
+    /// Any other registry-defined material
+    Material { kind: TileKind, hp: u16 },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -75,45 +93,95 @@ pub enum AntStructureType {
 }
 
 impl Tile {
+    /// A fresh, full-HP tile of the given kind
+    pub fn from_kind(kind: TileKind) -> Tile {
+        let hp = tile_registry().def_or_unknown(kind).hp;
+        let max_hp = hp;
+        let wall = |material| Tile::Wall {
+            hp,
+            max_hp,
+            material,
+        };
+        let floor = |material| Tile::Floor {
+            hp,
+            max_hp,
+            material,
+        };
+        let ore = |ore| Tile::Ore { hp, max_hp, ore };
+        let ant_structure = |structure_type| Tile::AntStructure { hp, structure_type };
+        match kind {
+            TileKind::AIR => Tile::Air,
+            TileKind::DIRT => Tile::Dirt { hp, max_hp },
+            TileKind::STONE => Tile::Stone { hp, max_hp },
+            TileKind::IRON_ORE => ore(OreType::Iron),
+            TileKind::TUNGSTEN_ORE => ore(OreType::Tungsten),
+            TileKind::WOOD_WALL => wall(BuildMaterial::Wood),
+            TileKind::STONE_WALL => wall(BuildMaterial::Stone),
+            TileKind::METAL_WALL => wall(BuildMaterial::Metal),
+            TileKind::WOOD_FLOOR => floor(BuildMaterial::Wood),
+            TileKind::STONE_FLOOR => floor(BuildMaterial::Stone),
+            TileKind::METAL_FLOOR => floor(BuildMaterial::Metal),
+            TileKind::RUBBLE => Tile::Rubble,
+            TileKind::ANT_TUNNEL => ant_structure(AntStructureType::Tunnel),
+            TileKind::ANT_NEST => ant_structure(AntStructureType::Nest),
+            TileKind::ANT_STORAGE => ant_structure(AntStructureType::Storage),
+            _ => Tile::Material { kind, hp },
+        }
+    }
+
+    /// Registry kind of this tile
+    pub fn kind(&self) -> TileKind {
+        match self {
+            Tile::Air => TileKind::AIR,
+            Tile::Dirt { .. } => TileKind::DIRT,
+            Tile::Stone { .. } => TileKind::STONE,
+            Tile::Ore { ore, .. } => TileKind::ore(*ore),
+            Tile::Wall { material, .. } => TileKind::wall(*material),
+            Tile::Floor { material, .. } => TileKind::floor(*material),
+            Tile::Rubble => TileKind::RUBBLE,
+            Tile::AntStructure { structure_type, .. } => TileKind::ant_structure(*structure_type),
+            Tile::Material { kind, .. } => *kind,
+        }
+    }
+
+    /// Registry data for this tile's kind
+    pub fn def(&self) -> &'static TileDef {
+        tile_registry().def_or_unknown(self.kind())
+    }
+
     /// Get the traversal cost for flow field pathfinding
     /// Key insight: Cost = HP, so damaged tiles are preferred paths
     pub fn traversal_cost(&self) -> u32 {
-        match self {
-            Tile::Air => 1,
-            Tile::Dirt { hp, .. } => *hp as u32,
-            Tile::Stone { hp, .. } => (*hp as u32) * 2, // Stone is harder
-            Tile::Wall { hp, .. } => *hp as u32,
-            Tile::Floor { hp, .. } => *hp as u32,
-            Tile::Rubble => 3, // Easy to traverse
-            Tile::AntStructure { .. } => 1, // Ants move freely through their structures
-        }
+        let def = self.def();
+        let hp = self.hp().unwrap_or(0) as f32;
+        (def.cost + (hp * def.cost_per_hp) as u32).max(1)
     }
 
     /// Can this tile be traversed at all?
     pub fn is_passable(&self) -> bool {
-        match self {
-            Tile::Air => true,
-            Tile::Rubble => true,
-            Tile::AntStructure { .. } => true,
-            _ => false, // Must dig/destroy to pass
-        }
+        self.def().passable
     }
 
     /// Can this tile be dug through?
     pub fn is_diggable(&self) -> bool {
-        matches!(self, Tile::Dirt { .. } | Tile::Stone { .. })
+        self.def().diggable
     }
 
     /// Can this tile be attacked/destroyed?
     pub fn is_destructible(&self) -> bool {
-        matches!(
-            self,
-            Tile::Dirt { .. }
-                | Tile::Stone { .. }
-                | Tile::Wall { .. }
-                | Tile::Floor { .. }
-                | Tile::AntStructure { .. }
-        )
+        self.def().destructible
+    }
+
+    /// Does this tile hold up what rests on it?
+    ///
+    /// Rubble and ant structures are loose and carry no load.
+    pub fn is_structural(&self) -> bool {
+        self.def().structural
+    }
+
+    /// How many tiles this tile can span sideways without support below
+    pub fn support_span(&self) -> i32 {
+        self.def().span
     }
 
     /// Get current HP if applicable
@@ -121,21 +189,42 @@ impl Tile {
         match self {
             Tile::Dirt { hp, .. } => Some(*hp),
             Tile::Stone { hp, .. } => Some(*hp),
+            Tile::Ore { hp, .. } => Some(*hp),
             Tile::Wall { hp, .. } => Some(*hp),
             Tile::Floor { hp, .. } => Some(*hp),
             Tile::AntStructure { hp, .. } => Some(*hp),
+            Tile::Material { hp, .. } => Some(*hp),
             _ => None,
         }
     }
 
+    /// Fraction of full HP remaining, if the tile tracks it
+    pub fn health(&self) -> Option<f32> {
+        let max_hp = match self {
+            Tile::Dirt { max_hp, .. }
+            | Tile::Stone { max_hp, .. }
+            | Tile::Ore { max_hp, .. }
+            | Tile::Wall { max_hp, .. }
+            | Tile::Floor { max_hp, .. } => *max_hp,
+            _ => self.def().hp,
+        };
+        let hp = self.hp()?;
+        (max_hp > 0).then(|| hp as f32 / max_hp as f32)
+    }
+
     /// Apply damage to tile, returns true if destroyed
     pub fn damage(&mut self, amount: u16) -> bool {
+        if !self.is_destructible() {
+            return false;
+        }
         match self {
             Tile::Dirt { hp, .. }
             | Tile::Stone { hp, .. }
+            | Tile::Ore { hp, .. }
             | Tile::Wall { hp, .. }
             | Tile::Floor { hp, .. }
-            | Tile::AntStructure { hp, .. } => {
+            | Tile::AntStructure { hp, .. }
+            | Tile::Material { hp, .. } => {
                 if *hp <= amount {
                     *self = Tile::Rubble;
                     true
@@ -150,22 +239,6 @@ impl Tile {
 
     /// ASCII representation for debug rendering
     pub fn to_ascii(&self) -> char {
-        match self {
-            Tile::Air => '.',
-            Tile::Dirt { .. } => ',',
-            Tile::Stone { .. } => '#',
-            Tile::Wall { material, .. } => match material {
-                BuildMaterial::Wood => '=',
-                BuildMaterial::Stone => 'H',
-                BuildMaterial::Metal => 'M',
-            },
-            Tile::Floor { .. } => '_',
-            Tile::Rubble => '%',
-            Tile::AntStructure { structure_type, .. } => match structure_type {
-                AntStructureType::Tunnel => 'o',
-                AntStructureType::Nest => 'O',
-                AntStructureType::Storage => 's',
-            },
-        }
+        self.def().glyph
     }
 }